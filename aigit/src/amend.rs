@@ -0,0 +1,66 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use std::error::Error;
+
+use crate::command::git_output;
+
+const TRAILER_PATTERN: &str = r"^[A-Za-z0-9][A-Za-z0-9-]*: \S";
+
+// 被修改提交的对比基准：HEAD^，根提交时为空 tree
+pub fn git_amend_base(force: bool) -> Result<String, Box<dyn Error>> {
+    let remotes = git_output(&["branch", "-r", "--format=%(refname:short)", "--contains", "HEAD"])?;
+    let remotes: Vec<&str> = remotes.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    if !remotes.is_empty() {
+        if !force {
+            return Err(format!(
+                "HEAD is already pushed to {}, amending it diverges from the published history; use --force to amend anyway",
+                remotes.join(", ")
+            )
+            .into());
+        }
+        eprintln!("Warning: amending a commit already on {}", remotes.join(", "));
+    }
+
+    if git_output(&["rev-parse", "-q", "--verify", "HEAD^"]).is_ok() {
+        return Ok(String::from("HEAD^"));
+    }
+    // 根提交没有父提交，和空 tree 比较
+    Ok(git_output(&["hash-object", "-t", "tree", "/dev/null"])?.trim().to_string())
+}
+
+// HEAD 的提交信息，拆成正文和末尾的 trailer（Signed-off-by、Refs 等）
+pub fn git_head_message() -> Result<(String, Vec<String>), Box<dyn Error>> {
+    let message = git_output(&["log", "-1", "--format=%B", "HEAD"])?;
+    let message = message.trim();
+    let re = Regex::new(TRAILER_PATTERN)?;
+
+    let Some((body, last)) = message.rsplit_once("\n\n") else {
+        return Ok((message.to_string(), vec![]));
+    };
+    if !last.lines().all(|line| re.is_match(line)) {
+        return Ok((message.to_string(), vec![]));
+    }
+    Ok((body.trim_end().to_string(), last.lines().map(|l| l.to_string()).collect()))
+}
+
+// 把原有的 trailer 补回新的提交信息，已经存在的不重复添加
+pub fn append_trailers(message: String, trailers: &[String]) -> String {
+    let missing: Vec<&String> = trailers.iter().filter(|t| !message.lines().any(|l| l.trim() == t.trim())).collect();
+    if missing.is_empty() {
+        return message;
+    }
+    let missing = missing.iter().map(|t| t.as_str()).collect::<Vec<&str>>().join("\n");
+
+    // 最后一段已经是 trailer 时合并进去，git 只把最后一段当作 trailer
+    let message = message.trim_end();
+    let last_is_trailers = match (Regex::new(TRAILER_PATTERN), message.rsplit_once("\n\n")) {
+        (Ok(re), Some((_, last))) => last.lines().all(|line| re.is_match(line)),
+        _ => false,
+    };
+    if last_is_trailers {
+        format!("{}\n{}", message, missing)
+    } else {
+        format!("{}\n\n{}", message, missing)
+    }
+}
@@ -0,0 +1,196 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_output, git_show, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+
+// 每个搜索条件最多返回的提交数
+const SEARCH_LIMIT: usize = 20;
+// 每个候选提交的 diff 最多保留的字符数
+const COMMIT_CHAR_LIMIT: usize = 4000;
+
+// 模型根据问题给出的搜索条件
+#[derive(Debug, Default, Deserialize)]
+struct HistorySearch {
+    // git log --grep，搜索提交信息
+    #[serde(default)]
+    grep: Vec<String>,
+    // git log -S，搜索增删的代码
+    #[serde(default)]
+    pickaxe: Vec<String>,
+    // 限定路径
+    #[serde(default)]
+    paths: Vec<String>,
+}
+
+fn prompt_search(question: String, files: String) -> Vec<ChatMessage> {
+    let mut search_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a Git expert. The user asks a question about the history of a repository. Derive searches that find the commits relevant to the question:
+    - `grep`: up to 5 case-insensitive regexes matched against commit messages (`git log --grep`)
+    - `pickaxe`: up to 5 literal strings whose number of occurrences in the code changed (`git log -S`), e.g. identifiers, crate or package names, config keys
+    - `paths`: optional path prefixes to limit the search to, only when the question clearly targets them; use the file list below
+
+Output ONLY a JSON object with no additional text and no markdown, for example:
+{\"grep\": [\"http client\", \"reqwest\"], \"pickaxe\": [\"reqwest::\", \"hyper::Client\"], \"paths\": []}"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    search_msgs[1].content.push_str(format!("Question: {}\n\n### Files in the repository\n{}", question, files).as_str());
+    search_msgs
+}
+
+fn prompt_answer(question: String, commits: String) -> Vec<ChatMessage> {
+    let mut answer_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer answering questions about the history of a repository. The user will provide a question and candidate commits (message and patch) found by searching the history.
+
+### Output Specification:
+    - Answer the question directly in the first sentence
+    - Support every claim with the short commit hash in backticks (e.g. `1a2b3c4`) and the date
+    - Prefer the reasons given in commit messages when explaining why
+    - Ignore candidates that are not relevant
+    - If the commits do not answer the question, say so; do not speculate about context not present in the commits"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    answer_msgs[1].content.push_str(format!("Question: {}\n\n### Candidate commits\n{}", question, commits).as_str());
+    answer_msgs
+}
+
+// 仓库中的目录列表，帮助模型给出路径条件
+fn git_tree_dirs() -> Result<String, Box<dyn Error>> {
+    let output = git_output(&["ls-tree", "-r", "-d", "--name-only", "HEAD"])?;
+    Ok(output.lines().take(200).collect::<Vec<&str>>().join("\n"))
+}
+
+fn ask_search(question: &str) -> Result<HistorySearch, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_search(question.to_string(), git_tree_dirs().unwrap_or_default()),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('{'), answer.rfind('}')) else {
+        return Err(format!("Fail to find the searches in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the searches in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+fn git_search(option: String, paths: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut args: Vec<String> = vec![
+        "log".to_string(),
+        "--no-color".to_string(),
+        "--format=%H".to_string(),
+        format!("-n{}", SEARCH_LIMIT),
+        "-i".to_string(),
+        option,
+    ];
+    if !paths.is_empty() {
+        args.push("--".to_string());
+        args.extend(paths.iter().cloned());
+    }
+
+    let output = git_output(&args)?;
+    Ok(output.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+}
+
+// 执行搜索，按命中次数排序，命中次数相同时保持 git log 的顺序（新的在前）
+fn find_candidates(search: &HistorySearch, number: usize) -> Vec<String> {
+    let mut options: Vec<String> = vec![];
+    options.extend(search.grep.iter().filter(|g| !g.trim().is_empty()).map(|g| format!("--grep={}", g)));
+    options.extend(search.pickaxe.iter().filter(|p| !p.trim().is_empty()).map(|p| format!("-S{}", p)));
+
+    let mut hits: Vec<(String, usize)> = vec![];
+    for option in options {
+        let hashes = match git_search(option.clone(), &search.paths) {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                debug!("search {} failed: {}", option, e);
+                continue;
+            }
+        };
+        for hash in hashes {
+            match hits.iter_mut().find(|(h, _)| *h == hash) {
+                Some((_, count)) => *count += 1,
+                None => hits.push((hash, 1)),
+            }
+        }
+    }
+
+    hits.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    hits.into_iter().take(number).map(|(hash, _)| hash).collect()
+}
+
+pub fn handle_ask(question: String, number: u32) -> Result<(), Box<dyn Error>> {
+    println!("Searching history...");
+    let search = ask_search(question.as_str())?;
+    debug!("history search: {:?}", search);
+
+    let candidates = find_candidates(&search, number as usize);
+    if candidates.is_empty() {
+        println!("No commits match the question.");
+        return Ok(());
+    }
+
+    let mut commits = String::new();
+    for hash in candidates.iter() {
+        // 逐个提交预处理，避免被忽略的文件吞掉后面提交的头部
+        let mut show = prepare_for_ai(git_show(hash.clone())?)?;
+        if show.len() > COMMIT_CHAR_LIMIT {
+            let mut end = COMMIT_CHAR_LIMIT;
+            while !show.is_char_boundary(end) {
+                end -= 1;
+            }
+            show.truncate(end);
+            show.push_str("\n[... patch truncated ...]\n");
+        }
+        commits.push_str(show.as_str());
+        commits.push('\n');
+    }
+
+    println!("============================================================================");
+    println!("Candidate Commits");
+    println!("============================================================================");
+    let mut args: Vec<String> = vec![
+        "log".to_string(),
+        "--no-walk=unsorted".to_string(),
+        "--date=short".to_string(),
+        "--format=%h %ad %s".to_string(),
+    ];
+    args.extend(candidates.iter().cloned());
+    println!("{}", git_output(&args)?.trim_end());
+
+    println!("============================================================================");
+    println!("Answering...\n");
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_answer(question, commits),
+    )?;
+    println!("{}", answer);
+
+    Ok(())
+}
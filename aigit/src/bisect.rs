@@ -0,0 +1,230 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+use std::process::{Command, Stdio};
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_foreground, git_output, git_show, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::conventional::git_range_commits;
+
+// 失败输出只保留末尾部分，测试输出的关键信息通常在最后
+const FAILURE_CHAR_LIMIT: usize = 6000;
+
+// 模型给出的可疑提交
+#[derive(Debug, Deserialize)]
+struct Suspect {
+    hash: String,
+    #[serde(default)]
+    reason: String,
+}
+
+fn prompt_rank(commits: String, symptom: Option<String>) -> Vec<ChatMessage> {
+    let mut rank_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer helping to find the commit that introduced a regression with `git bisect`. The user will provide the commits between the last known good and the first known bad revision, each with its hash, message and changed files, and optionally a description of the failure.
+
+Rank the commits from most to least likely to have introduced the regression, consider:
+    - whether the changed files and the message relate to the failure
+    - risky changes such as dependency upgrades, refactors and changes in error handling
+    - commits that only touch documentation or tests are unlikely culprits
+
+Do not speculate about context not present in the commits.
+Output ONLY a JSON array with no additional text and no markdown, most suspicious first, at most 10 items, for example:
+[{\"hash\": \"1a2b3c4\", \"reason\": \"Changes the timeout handling in the HTTP client\"}]"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    if let Some(symptom) = symptom {
+        rank_msgs[1].content.push_str(format!("### Failure\n{}\n\n", symptom).as_str());
+    }
+    rank_msgs[1].content.push_str("### Commits\n");
+    rank_msgs[1].content.push_str(commits.as_str());
+    rank_msgs
+}
+
+fn prompt_culprit(culprit: String, command: String, failure: String) -> Vec<ChatMessage> {
+    let mut culprit_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer analyzing a regression found by `git bisect`. The user will provide the first bad commit (message and patch), the test command and the output of the command at that commit.
+
+### Output Specification:
+    - Explain in 1-3 sentences how the commit causes the failure
+    - Quote the most likely faulty hunk from the patch (file and `@@` header) and explain which lines are wrong
+    - Suggest a fix
+    - If the patch does not explain the failure (e.g. a flaky test), say so; do not speculate about context not present in the input"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    culprit_msgs[1].content.push_str(format!("### Test command\n{}\n", command).as_str());
+    culprit_msgs[1].content.push_str("\n### Failure output\n");
+    culprit_msgs[1].content.push_str(failure.as_str());
+    culprit_msgs[1].content.push_str("\n### First bad commit\n");
+    culprit_msgs[1].content.push_str(culprit.as_str());
+    culprit_msgs
+}
+
+// 二分查找会切换提交，工作区有未提交的修改时拒绝执行
+fn ensure_clean_worktree() -> Result<(), Box<dyn Error>> {
+    let status = git_output(&["status", "--porcelain", "--untracked-files=no"])?;
+    if !status.trim().is_empty() {
+        return Err("The working tree has uncommitted changes, commit or stash them before bisecting".into());
+    }
+    Ok(())
+}
+
+fn rank_suspects(good: &str, bad: &str, symptom: Option<String>) -> Result<(), Box<dyn Error>> {
+    let commits = git_range_commits(format!("{}..{}", good, bad).as_str())?;
+    if commits.is_empty() {
+        return Err(format!("No commits in {}..{}", good, bad).into());
+    }
+
+    let mut content = String::new();
+    for commit in commits.iter() {
+        let stat = git_output(&["show", "--no-color", "--stat", "--format=", commit.hash.as_str()])?;
+        content.push_str(
+            format!(
+                "\n### {}\n{}\n{}\n{}\n",
+                &commit.hash[..12.min(commit.hash.len())],
+                commit.subject,
+                commit.body,
+                stat
+            )
+            .as_str(),
+        );
+    }
+
+    eprintln!("Ranking {} commit(s) in {}..{}...", commits.len(), good, bad);
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_rank(prepare_for_ai(content)?, symptom),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Err(format!("Fail to find the ranking in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the ranking in the response:\n{}", answer).into());
+    }
+    let suspects: Vec<Suspect> = serde_json::from_str(&answer[start..=end])?;
+
+    println!("============================================================================");
+    println!("Suspicious Commits");
+    println!("============================================================================");
+    for (idx, suspect) in suspects.iter().enumerate() {
+        let Some(commit) = commits
+            .iter()
+            .find(|c| !suspect.hash.trim().is_empty() && c.hash.starts_with(suspect.hash.trim()))
+        else {
+            debug!("unknown commit {} in ranking", suspect.hash);
+            continue;
+        };
+        println!("{:>2}. {} {}", idx + 1, &commit.hash[..7.min(commit.hash.len())], commit.subject);
+        if !suspect.reason.is_empty() {
+            println!("    {}", suspect.reason);
+        }
+    }
+    println!(
+        "\nStart bisecting with `git bisect start {} {}`, then test the commits above first with `git checkout <hash>`,",
+        bad, good
+    );
+    println!("or let aigit drive it with `aigit bisect --good {} --bad {} --run <cmd>`.", good, bad);
+    Ok(())
+}
+
+// 在当前提交上执行测试命令，合并标准输出和错误输出
+fn run_command(command: &str) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("sh")
+        .arg("-c")
+        // 整体重定向，`a && b` 等复合命令中每一步的 stderr 都要合并
+        .arg(format!("{{\n{}\n}} 2>&1", command))
+        .stdin(Stdio::null())
+        .output()?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(format!("\n[{}]\n", output.status).as_str());
+    Ok(text)
+}
+
+fn tail_chars(text: &str, limit: usize) -> String {
+    if text.len() <= limit {
+        return text.to_string();
+    }
+    let mut start = text.len() - limit;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... output truncated ...]\n{}", &text[start..])
+}
+
+// 执行 git bisect run，返回第一个坏提交，以及它上面的测试输出
+fn run_bisect(good: &str, bad: &str, command: &str) -> Result<(String, String), Box<dyn Error>> {
+    git_output(&["bisect", "start", bad, good])?;
+
+    // git bisect run 的输出直接显示给用户
+    git_foreground(
+        &["bisect", "run", "sh", "-c", command],
+        "git bisect run could not find the first bad commit",
+    )?;
+
+    let culprit = git_output(&["rev-parse", "--verify", "refs/bisect/bad"])?.trim().to_string();
+    git_output(&["checkout", "-q", culprit.as_str()])?;
+    let failure = run_command(command)?;
+    Ok((culprit, failure))
+}
+
+pub fn handle_bisect(
+    good: String,
+    bad: String,
+    run: Option<String>,
+    symptom: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(command) = run else {
+        return rank_suspects(good.as_str(), bad.as_str(), symptom);
+    };
+
+    ensure_clean_worktree()?;
+    let result = run_bisect(good.as_str(), bad.as_str(), command.as_str());
+    // 无论成功与否都结束二分查找，回到原来的分支
+    if let Err(e) = git_output(&["bisect", "reset"]) {
+        eprintln!("Fail to reset bisect: {}", e);
+    }
+    let (culprit, failure) = result?;
+
+    println!("============================================================================");
+    println!("First Bad Commit");
+    println!("============================================================================");
+    println!("{}", git_output(&["log", "-1", "--date=short", "--format=%h %ad %an %s", culprit.as_str()])?.trim_end());
+
+    println!("============================================================================");
+    println!("Explaining...\n");
+    let mut failure = tail_chars(failure.as_str(), FAILURE_CHAR_LIMIT);
+    if let Some(symptom) = symptom {
+        failure = format!("{}\n\nReported symptom: {}", failure, symptom);
+    }
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let explanation = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_culprit(prepare_for_ai(git_show(culprit)?)?, command, prepare_for_ai(failure)?),
+    )?;
+    println!("{}", explanation);
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use serde::Deserialize;
+use std::error::Error;
+use std::io::IsTerminal;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_diff, git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_BRANCH_PATTERN};
+use crate::split::read_line;
+use crate::ticket::extract_tickets;
+
+// slug 最多保留的字符数
+const SLUG_CHAR_LIMIT: usize = 40;
+
+// 模型给出的分支名组成部分
+#[derive(Debug, Deserialize)]
+struct BranchSuggestion {
+    #[serde(rename = "type")]
+    kind: String,
+    slug: String,
+}
+
+// 分支名和检查结果
+struct Candidate {
+    name: String,
+    // 不可用的原因，None 表示可用
+    problem: Option<String>,
+}
+
+fn prompt_branch(intent: String) -> Vec<ChatMessage> {
+    let mut branch_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer naming a Git branch. The user will provide either a description of the planned work or the Git patch of the staged changes.
+
+Suggest 3 different branch names, each with:
+    - `type`: one of feat, fix, docs, style, refactor, test, chore, perf
+    - `slug`: 2-5 lowercase English words joined by `-` that summarize the work, e.g. `add-oauth-login`
+
+Do not include ticket ids in the slug.
+Output ONLY a JSON array with no additional text and no markdown, best suggestion first, for example:
+[{\"type\": \"feat\", \"slug\": \"add-oauth-login\"}]"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    branch_msgs[1].content.push_str(intent.as_str());
+    branch_msgs
+}
+
+// 只保留小写字母、数字和 `-`
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let mut slug: String = slug.chars().take(SLUG_CHAR_LIMIT).collect();
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+// 按配置的格式生成分支名
+fn render_name(kind: &str, ticket: Option<&str>, slug: &str) -> Result<String, Box<dyn Error>> {
+    let mut name = G_BRANCH_PATTERN.replace("<type>", slugify(kind).as_str()).replace("<slug>", slug);
+    match ticket {
+        Some(ticket) => name = name.replace("<ticket>", ticket),
+        None => name = Regex::new(r"<ticket>[-_./]?")?.replace_all(name.as_str(), "").into_owned(),
+    }
+    Ok(name.trim_matches(['-', '_', '/', '.']).to_string())
+}
+
+// 本地和远程已存在的分支名，远程分支去掉远程名前缀
+fn git_existing_branches() -> Result<Vec<String>, Box<dyn Error>> {
+    let output = git_output(&[
+        "for-each-ref",
+        "--format=%(refname)",
+        "refs/heads",
+        "refs/remotes",
+    ])?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(name) = line.strip_prefix("refs/heads/") {
+                return Some(name.to_string());
+            }
+            let (_, name) = line.strip_prefix("refs/remotes/")?.split_once('/')?;
+            Some(name.to_string())
+        })
+        .filter(|name| name != "HEAD")
+        .collect())
+}
+
+fn check_name(name: &str, existing: &[String]) -> Option<String> {
+    if git_output(&["check-ref-format", "--branch", name]).is_err() {
+        return Some(String::from("invalid branch name"));
+    }
+    if existing.iter().any(|e| e == name) {
+        return Some(String::from("already exists"));
+    }
+    None
+}
+
+fn ask_suggestions(intent: String) -> Result<Vec<BranchSuggestion>, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(G_AI_MODEL.clone(), G_AI_API_KEY.clone(), prompt_branch(prepare_for_ai(intent)?))?;
+
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Err(format!("Fail to find branch names in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find branch names in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+fn git_switch_create(name: &str) -> Result<(), Box<dyn Error>> {
+    git_output(&["switch", "-c", name])?;
+    println!("Switched to a new branch '{}'", name);
+    Ok(())
+}
+
+pub fn handle_branch(
+    description: Option<String>,
+    from_staged: bool,
+    ticket: Option<String>,
+    create: bool,
+) -> Result<(), Box<dyn Error>> {
+    let intent = match (description.as_deref(), from_staged) {
+        (Some(description), false) => format!("Description of the planned work:\n{}", description),
+        (None, true) => {
+            let diff_content = git_diff("HEAD".to_string(), true)?;
+            if diff_content.trim().is_empty() {
+                return Err("Nothing staged to name a branch after".into());
+            }
+            format!("Git patch of the staged changes:\n{}", diff_content)
+        }
+        _ => return Err("Give either a description or --from-staged".into()),
+    };
+    let ticket = match ticket {
+        Some(ticket) => Some(ticket),
+        None => extract_tickets(description.as_deref().unwrap_or_default())?.into_iter().next(),
+    };
+
+    eprintln!("Suggesting branch names...");
+    let suggestions = ask_suggestions(intent)?;
+    let existing = git_existing_branches()?;
+    let mut candidates: Vec<Candidate> = vec![];
+    for suggestion in suggestions.iter() {
+        let slug = slugify(suggestion.slug.as_str());
+        if slug.is_empty() {
+            continue;
+        }
+        let name = render_name(suggestion.kind.as_str(), ticket.as_deref(), slug.as_str())?;
+        if candidates.iter().any(|c| c.name == name) {
+            continue;
+        }
+        let problem = check_name(name.as_str(), &existing);
+        candidates.push(Candidate { name, problem });
+    }
+
+    println!("============================================================================");
+    println!("Branch Names");
+    println!("============================================================================");
+    for (idx, candidate) in candidates.iter().enumerate() {
+        match &candidate.problem {
+            Some(problem) => println!("{:>2}. {} ({})", idx + 1, candidate.name, problem),
+            None => println!("{:>2}. {}", idx + 1, candidate.name),
+        }
+    }
+
+    let available: Vec<&Candidate> = candidates.iter().filter(|c| c.problem.is_none()).collect();
+    let Some(first) = available.first() else {
+        return Err("No usable branch name, try a different description".into());
+    };
+    if create {
+        return git_switch_create(first.name.as_str());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    loop {
+        let input = read_line("Create and switch to a branch [number, Enter to skip]: ")?;
+        if input.is_empty() {
+            return Ok(());
+        }
+        match input.parse::<usize>().ok().and_then(|n| candidates.get(n.wrapping_sub(1))) {
+            Some(candidate) if candidate.problem.is_none() => return git_switch_create(candidate.name.as_str()),
+            Some(candidate) => println!("{} {}.", candidate.name, candidate.problem.as_deref().unwrap_or_default()),
+            None => println!("Unknown branch number."),
+        }
+    }
+}
@@ -0,0 +1,368 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use serde::Deserialize;
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_diff, git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::conventional::{RangeCommit, git_range_commits};
+
+const SEMVER_PATTERN: &str = r"^(?P<prefix>v?)(?P<major>\d+)\.(?P<minor>\d+)\.(?P<patch>\d+)$";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+
+    fn parse(s: &str) -> Bump {
+        match s.trim().to_lowercase().as_str() {
+            "major" => Bump::Major,
+            "minor" => Bump::Minor,
+            "patch" => Bump::Patch,
+            _ => Bump::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Version {
+    prefix: String,
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl Version {
+    fn parse(tag: &str) -> Option<Version> {
+        let re = Regex::new(SEMVER_PATTERN).ok()?;
+        let caps = re.captures(tag.trim())?;
+        Some(Version {
+            prefix: caps["prefix"].to_string(),
+            major: caps["major"].parse().ok()?,
+            minor: caps["minor"].parse().ok()?,
+            patch: caps["patch"].parse().ok()?,
+        })
+    }
+
+    // 0.x 版本中不兼容修改只升级 minor，新功能升级 patch
+    fn bump(&self, bump: Bump) -> Version {
+        let mut next = self.clone();
+        let bump = match (self.major, bump) {
+            (0, Bump::Major) => Bump::Minor,
+            (0, Bump::Minor) => Bump::Patch,
+            _ => bump,
+        };
+        match bump {
+            Bump::Major => {
+                next.major += 1;
+                next.minor = 0;
+                next.patch = 0;
+            }
+            Bump::Minor => {
+                next.minor += 1;
+                next.patch = 0;
+            }
+            Bump::Patch => next.patch += 1,
+            Bump::None => {}
+        }
+        next
+    }
+
+    fn number(&self) -> String {
+        format!("{}.{}.{}", self.major, self.minor, self.patch)
+    }
+
+    fn tag(&self) -> String {
+        format!("{}{}", self.prefix, self.number())
+    }
+}
+
+// 模型对 API 变更的判断
+#[derive(Debug, Deserialize)]
+struct ApiJudgement {
+    bump: String,
+    #[serde(default)]
+    breaking: bool,
+    #[serde(default)]
+    justification: String,
+}
+
+fn prompt_version_bump(commits: String, diff_content: String) -> Vec<ChatMessage> {
+    let mut bump_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a release manager applying semantic versioning. The user will provide the commits since the last release and the combined Git patch.
+
+Judge the changes to the PUBLIC API (exported functions, types, CLI options, configuration keys, file formats):
+    - major: an existing public API was removed, renamed or changed incompatibly
+    - minor: new public API was added in a backward compatible way
+    - patch: only backward compatible bug fixes or internal changes
+    - none: nothing that affects users
+
+Do not speculate about context not present in the patch.
+Output ONLY a JSON object with no additional text and no markdown, for example:
+{\"bump\": \"minor\", \"breaking\": false, \"justification\": \"Adds the `--format` option; existing options are unchanged\"}"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: "Please judge the following release. \n### Commits\n".to_string(),
+        },
+    ];
+
+    bump_msgs[1].content.push_str(commits.as_str());
+    bump_msgs[1].content.push_str("\n### Patch\n");
+    bump_msgs[1].content.push_str(diff_content.as_str());
+    bump_msgs
+}
+
+// HEAD 可达的最新 semver tag
+fn git_last_semver_tag() -> Result<Option<String>, Box<dyn Error>> {
+    let output = git_output(&["tag", "--merged", "HEAD", "--sort=-v:refname"])?;
+    Ok(output
+        .lines()
+        .map(|tag| tag.trim())
+        .find(|tag| Version::parse(tag).is_some())
+        .map(|tag| tag.to_string()))
+}
+
+// 根据 conventional commits 得出的最低升级级别
+fn conventional_bump(commits: &[RangeCommit]) -> Bump {
+    commits
+        .iter()
+        .map(|commit| {
+            if commit.breaking {
+                return Bump::Major;
+            }
+            match commit.kind.as_deref() {
+                Some("feat") => Bump::Minor,
+                Some(_) => Bump::Patch,
+                None => Bump::Patch,
+            }
+        })
+        .max()
+        .unwrap_or(Bump::None)
+}
+
+// diff_range 必须是两端的形式，单独的 `HEAD` 会变成和工作区比较
+fn ask_api_judgement(commits: &[RangeCommit], diff_range: &str) -> Result<ApiJudgement, Box<dyn Error>> {
+    let commit_list = commits
+        .iter()
+        .map(|c| format!("- {} {}\n{}", &c.hash[..7.min(c.hash.len())], c.subject, c.body))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let diff_content = git_diff(diff_range.to_string(), false)?;
+
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_version_bump(prepare_for_ai(commit_list)?, prepare_for_ai(diff_content)?),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('{'), answer.rfind('}')) else {
+        return Err(format!("Fail to find the judgement in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the judgement in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+// 更新当前目录下 Cargo.toml 中 [package] 的 version
+fn write_cargo_version(path: &Path, version: &str) -> Result<bool, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let re = Regex::new(r#"^(\s*version\s*=\s*)"[^"]*""#)?;
+
+    let mut in_package = false;
+    let mut updated = false;
+    let mut lines: Vec<String> = vec![];
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+        }
+        if in_package && !updated && re.is_match(line) {
+            lines.push(re.replace(line, format!("${{1}}\"{}\"", version).as_str()).into_owned());
+            updated = true;
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+
+    if updated {
+        let mut new_content = lines.join("\n");
+        if content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        std::fs::write(path, new_content)?;
+    }
+    Ok(updated)
+}
+
+// 更新 package.json 中的 version，保留原有格式
+fn write_npm_version(path: &Path, version: &str) -> Result<bool, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let re = Regex::new(r#"("version"\s*:\s*)"[^"]*""#)?;
+    if !re.is_match(content.as_str()) {
+        return Ok(false);
+    }
+    let new_content = re.replace(content.as_str(), format!("${{1}}\"{}\"", version).as_str());
+    std::fs::write(path, new_content.as_ref())?;
+    Ok(true)
+}
+
+// 返回被更新的文件
+// Cargo.lock 中记录了 workspace 成员的版本，同步更新后一起提交
+fn update_cargo_lock() -> Option<String> {
+    let manifest = Command::new("cargo")
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let manifest = String::from_utf8_lossy(&manifest.stdout).trim().to_string();
+    let lock = Path::new(manifest.as_str()).parent()?.join("Cargo.lock");
+    if !lock.exists() {
+        return None;
+    }
+
+    // --workspace 只更新 workspace 成员，不升级依赖
+    let status = Command::new("cargo").args(["update", "--workspace", "--offline", "--quiet"]).status();
+    if !status.is_ok_and(|s| s.success()) {
+        eprintln!("Warning: failed to update {}, run `cargo update --workspace` yourself", lock.display());
+        return None;
+    }
+    println!("Updated {}", lock.display());
+    Some(lock.to_string_lossy().into_owned())
+}
+
+fn write_versions(version: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut written: Vec<String> = vec![];
+    let cargo = Path::new("Cargo.toml");
+    if cargo.exists() && write_cargo_version(cargo, version)? {
+        println!("Updated Cargo.toml to {}", version);
+        written.push(String::from("Cargo.toml"));
+        written.extend(update_cargo_lock());
+    }
+    let npm = Path::new("package.json");
+    if npm.exists() && write_npm_version(npm, version)? {
+        println!("Updated package.json to {}", version);
+        written.push(String::from("package.json"));
+    }
+    if written.is_empty() {
+        return Err("No Cargo.toml or package.json with a version found in the current directory".into());
+    }
+    Ok(written)
+}
+
+// 提交版本号修改，使 tag 指向包含新版本号的提交
+fn git_commit_release(files: &[String], tag: &str) -> Result<(), Box<dyn Error>> {
+    let mut args: Vec<String> = vec![String::from("add"), String::from("--")];
+    args.extend(files.iter().cloned());
+    git_output(&args)?;
+    // --only 只提交版本文件，用户已暂存的其他修改留在暂存区
+    let mut args: Vec<String> = vec![
+        String::from("commit"),
+        String::from("-m"),
+        format!("chore(release): {}", tag),
+        String::from("--only"),
+        String::from("--"),
+    ];
+    args.extend(files.iter().cloned());
+    git_output(&args)?;
+    println!("Committed version bump");
+    Ok(())
+}
+
+fn git_annotated_tag(tag: &str, message: &str) -> Result<(), Box<dyn Error>> {
+    git_output(&["tag", "-a", tag, "-m", message])?;
+    println!("Created tag {}", tag);
+    Ok(())
+}
+
+pub fn handle_version_bump(since: Option<String>, write: bool, tag: bool) -> Result<(), Box<dyn Error>> {
+    let since = match since {
+        Some(since) => Some(since),
+        None => git_last_semver_tag()?,
+    };
+    let current = match since.as_deref() {
+        Some(since) => Version::parse(since).ok_or(format!("'{}' is not a semver tag", since))?,
+        None => Version { prefix: String::from("v"), major: 0, minor: 0, patch: 0 },
+    };
+    let range = match since.as_deref() {
+        Some(since) => format!("{}..HEAD", since),
+        None => String::from("HEAD"),
+    };
+    // 没有 tag 时从空 tree 开始比较，不能用 `git diff HEAD`
+    let diff_range = match since.as_deref() {
+        Some(_) => range.clone(),
+        None => format!("{}..HEAD", git_output(&["hash-object", "-t", "tree", "/dev/null"])?.trim()),
+    };
+
+    let commits = git_range_commits(range.as_str())?;
+    if commits.is_empty() {
+        println!("No commits since {}, nothing to release.", since.unwrap_or_default());
+        return Ok(());
+    }
+
+    let flagged: Vec<&RangeCommit> = commits.iter().filter(|c| c.breaking).collect();
+    let rule_bump = conventional_bump(&commits);
+
+    eprintln!("Judging public API changes in {}...", range);
+    let judgement = ask_api_judgement(&commits, diff_range.as_str())?;
+    let ai_bump = if judgement.breaking { Bump::Major } else { Bump::parse(judgement.bump.as_str()) };
+    let bump = rule_bump.max(ai_bump);
+    let next = current.bump(bump);
+
+    println!("============================================================================");
+    println!("Version Bump");
+    println!("============================================================================");
+    println!("Current version: {}", since.as_deref().unwrap_or("none"));
+    println!("Commits: {}", commits.len());
+    println!("Conventional commits suggest: {}", rule_bump.as_str());
+    if !flagged.is_empty() {
+        println!("Breaking changes flagged in commits:");
+        for commit in flagged.iter() {
+            println!("  {} {}", &commit.hash[..7.min(commit.hash.len())], commit.subject);
+        }
+    }
+    println!("AI judgement of public API: {}", ai_bump.as_str());
+    if !judgement.justification.is_empty() {
+        println!("  {}", judgement.justification);
+    }
+    println!("\nRecommended next version: {} ({})", next.tag(), bump.as_str());
+
+    if bump == Bump::None {
+        return Ok(());
+    }
+    if write {
+        let files = write_versions(next.number().as_str())?;
+        if tag {
+            git_commit_release(&files, next.tag().as_str())?;
+        }
+    }
+    if tag {
+        let message = format!("Release {}\n\n{}", next.tag(), judgement.justification);
+        git_annotated_tag(next.tag().as_str(), message.trim())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,330 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+use std::path::Path;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_output, is_poor_message, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::conventional::{RangeCommit, git_range_commits};
+
+// (分组标题, 对应的提交类型)。未列出的类型（chore、ci、test 等）不面向用户，不写入 changelog
+const SECTIONS: [(&str, &[&str]); 5] = [
+    ("Features", &["feat"]),
+    ("Fixes", &["fix"]),
+    ("Performance", &["perf"]),
+    ("Documentation", &["docs"]),
+    ("Changed", &["refactor", "revert"]),
+];
+const BREAKING_SECTION: &str = "Breaking Changes";
+const UNRELEASED_HEADING: &str = "## [Unreleased]";
+
+const CHANGELOG_HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/).
+";
+
+// 模型改写后的条目
+#[derive(Debug, Deserialize)]
+struct RewrittenEntry {
+    hash: String,
+    #[serde(rename = "type")]
+    kind: String,
+    entry: String,
+}
+
+// changelog 中的一条记录
+struct Entry {
+    section: String,
+    scope: Option<String>,
+    text: String,
+    hash: String,
+}
+
+fn prompt_changelog(commits: String) -> Vec<ChatMessage> {
+    let mut changelog_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a release manager writing a changelog for users of the project. The user will provide commits whose messages are terse or do not follow conventional commits, each with its hash, message and changed files.
+
+For every commit:
+    - `hash`: the hash exactly as given
+    - `type`: one of feat, fix, perf, docs, refactor, chore, test, ci, build
+    - `entry`: a user-facing changelog entry, imperative tense, ≤100 characters, no ending punctuation
+
+Do not speculate about context not present in the commits.
+Output ONLY a JSON array with no additional text and no markdown, for example:
+[{\"hash\": \"1a2b3c4\", \"type\": \"fix\", \"entry\": \"Prevent crash when the config file is empty\"}]"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: "Please rewrite the following commits. \n".to_string(),
+        },
+    ];
+
+    changelog_msgs[1].content.push_str(commits.as_str());
+    changelog_msgs
+}
+
+// 需要模型改写的提交：不符合 conventional commits，或描述过于简短
+fn needs_rewrite(commit: &RangeCommit) -> bool {
+    commit.kind.is_none() || is_poor_message(commit.description.as_str())
+}
+
+fn rewrite_commits(commits: &[&RangeCommit]) -> Result<Vec<RewrittenEntry>, Box<dyn Error>> {
+    let mut content = String::new();
+    for commit in commits.iter() {
+        let stat = git_output(&["show", "--no-color", "--stat", "--format=", commit.hash.as_str()])?;
+        content.push_str(
+            format!(
+                "\n### {}\n{}\n{}\n{}\n",
+                &commit.hash[..12.min(commit.hash.len())],
+                commit.subject,
+                commit.body,
+                stat
+            )
+            .as_str(),
+        );
+    }
+
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_changelog(prepare_for_ai(content)?),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Err(format!("Fail to find changelog entries in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find changelog entries in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+fn section_of(kind: &str) -> Option<&'static str> {
+    SECTIONS
+        .iter()
+        .find(|(_, kinds)| kinds.contains(&kind))
+        .map(|(section, _)| *section)
+}
+
+// 首字母大写
+fn capitalize(text: &str) -> String {
+    let mut chars = text.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn collect_entries(commits: &[RangeCommit]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let to_rewrite: Vec<&RangeCommit> = commits.iter().filter(|c| needs_rewrite(c)).collect();
+    let rewritten = if to_rewrite.is_empty() {
+        vec![]
+    } else {
+        eprintln!("Rewriting {} commit message(s)...", to_rewrite.len());
+        rewrite_commits(&to_rewrite)?
+    };
+
+    let mut entries: Vec<Entry> = vec![];
+    for commit in commits.iter() {
+        let short = &commit.hash[..7.min(commit.hash.len())];
+        let (kind, text) = match rewritten
+            .iter()
+            .find(|r| !r.hash.is_empty() && commit.hash.starts_with(r.hash.trim()))
+            .filter(|_| needs_rewrite(commit))
+        {
+            Some(r) => (r.kind.trim().to_lowercase(), r.entry.clone()),
+            None => (commit.kind.clone().unwrap_or_default(), commit.description.clone()),
+        };
+
+        let section = if commit.breaking {
+            Some(BREAKING_SECTION)
+        } else {
+            section_of(kind.as_str())
+        };
+        let Some(section) = section else {
+            debug!("skip {} ({}) in changelog", short, kind);
+            continue;
+        };
+
+        entries.push(Entry {
+            section: section.to_string(),
+            scope: commit.scope.clone(),
+            text: capitalize(text.as_str()),
+            hash: short.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+// 版本标题：HEAD 为 Unreleased，否则使用 <to> 和它的日期
+fn version_heading(to: &str) -> Result<String, Box<dyn Error>> {
+    if to == "HEAD" {
+        return Ok(String::from(UNRELEASED_HEADING));
+    }
+    let date = git_output(&["log", "-1", "--format=%ad", "--date=short", to])?;
+    Ok(format!("## [{}] - {}", to.trim_start_matches('v'), date.trim()))
+}
+
+fn render_section(heading: String, entries: &[Entry]) -> String {
+    let mut md = format!("{}\n", heading);
+
+    let mut sections: Vec<&str> = vec![BREAKING_SECTION];
+    sections.extend(SECTIONS.iter().map(|(section, _)| *section));
+    for section in sections {
+        let items: Vec<&Entry> = entries.iter().filter(|e| e.section == section).collect();
+        if items.is_empty() {
+            continue;
+        }
+        md.push_str(format!("\n### {}\n\n", section).as_str());
+        for item in items {
+            match &item.scope {
+                Some(scope) => md.push_str(format!("- **{}:** {} ({})\n", scope, item.text, item.hash).as_str()),
+                None => md.push_str(format!("- {} ({})\n", item.text, item.hash).as_str()),
+            }
+        }
+    }
+    md
+}
+
+// 把新生成的条目合并到已有的 Unreleased 段，按分组插入，已有的提交不重复添加
+fn merge_unreleased(existing: &[&str], section: &str) -> Vec<String> {
+    let mut merged: Vec<String> = existing.iter().map(|line| line.to_string()).collect();
+    let mut title = "";
+    for line in section.lines() {
+        if let Some(heading) = line.strip_prefix("### ") {
+            title = heading;
+            continue;
+        }
+        if !line.starts_with("- ") {
+            continue;
+        }
+        // 条目以 (短 hash) 结尾
+        if let Some((_, hash)) = line.rsplit_once(" (")
+            && merged.iter().any(|l| l.starts_with("- ") && l.ends_with(format!(" ({}", hash).as_str()))
+        {
+            continue;
+        }
+
+        let heading = format!("### {}", title);
+        match merged.iter().position(|l| *l == heading) {
+            Some(pos) => {
+                // 插到该分组最后一条之后
+                let mut end = pos + 1;
+                while end < merged.len() && !merged[end].starts_with("### ") {
+                    end += 1;
+                }
+                while end > pos + 1 && merged[end - 1].trim().is_empty() {
+                    end -= 1;
+                }
+                merged.insert(end, line.to_string());
+            }
+            None => {
+                while merged.last().is_some_and(|l| l.trim().is_empty()) {
+                    merged.pop();
+                }
+                merged.extend([String::new(), heading, String::new(), line.to_string()]);
+            }
+        }
+    }
+    merged
+}
+
+// 把新的版本段插入到第一个版本标题之前，文件不存在时创建；已有 Unreleased 段时合并进去
+fn prepend_changelog(path: &str, section: &str) -> Result<(), Box<dyn Error>> {
+    let content = if Path::new(path).exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::from(CHANGELOG_HEADER)
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let unreleased = lines
+        .iter()
+        .position(|line| line.to_lowercase().starts_with(UNRELEASED_HEADING.to_lowercase().as_str()));
+    if section.starts_with(UNRELEASED_HEADING)
+        && let Some(start) = unreleased
+    {
+        let end = lines[start + 1..]
+            .iter()
+            .position(|line| line.starts_with("## "))
+            .map(|pos| start + 1 + pos)
+            .unwrap_or(lines.len());
+        let mut new_lines: Vec<String> = lines[..start].iter().map(|line| line.to_string()).collect();
+        new_lines.extend(merge_unreleased(&lines[start..end], section));
+        if end < lines.len() {
+            if new_lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                new_lines.push(String::new());
+            }
+            new_lines.extend(lines[end..].iter().map(|line| line.to_string()));
+        }
+        std::fs::write(path, format!("{}\n", new_lines.join("\n").trim_end()))?;
+        return Ok(());
+    }
+
+    let pos = lines.iter().position(|line| line.starts_with("## "));
+    let new_content = match pos {
+        Some(pos) => format!(
+            "{}\n\n{}\n{}\n",
+            lines[..pos].join("\n").trim_end(),
+            section,
+            lines[pos..].join("\n")
+        ),
+        None => format!("{}\n\n{}", content.trim_end(), section),
+    };
+
+    std::fs::write(path, new_content)?;
+    Ok(())
+}
+
+pub fn handle_changelog(range: String, prepend: Option<String>) -> Result<(), Box<dyn Error>> {
+    if range.contains("...") {
+        return Err(format!("Symmetric range '{}' is not supported, use <from>..<to>", range).into());
+    }
+    let (from, to) = match range.split_once("..") {
+        Some((from, "")) => (from.to_string(), String::from("HEAD")),
+        Some((from, to)) => (from.to_string(), to.to_string()),
+        None => (range.clone(), String::from("HEAD")),
+    };
+
+    let commits = git_range_commits(format!("{}..{}", from, to).as_str())?;
+    if commits.is_empty() {
+        return Err(format!("No commits in {}..{}", from, to).into());
+    }
+
+    let entries = collect_entries(&commits)?;
+    let section = render_section(version_heading(to.as_str())?, &entries);
+
+    match prepend {
+        Some(path) => {
+            prepend_changelog(path.as_str(), section.as_str())?;
+            println!("Changelog section inserted into {}", path);
+        }
+        None => print!("{}", section),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_into_existing_unreleased() {
+        let existing = "## [Unreleased]\n\n### Fixes\n\n- Old fix (1111111)\n";
+        let section = "## [Unreleased]\n\n### Features\n\n- New feature (3333333)\n\n### Fixes\n\n- Old fix (1111111)\n- New fix (2222222)\n";
+        let lines: Vec<&str> = existing.lines().collect();
+        assert_eq!(
+            merge_unreleased(&lines, section).join("\n"),
+            "## [Unreleased]\n\n### Fixes\n\n- Old fix (1111111)\n- New fix (2222222)\n\n### Features\n\n- New feature (3333333)"
+        );
+    }
+}
@@ -2,11 +2,37 @@ use clap::Parser;
 #[allow(unused_imports)]
 use log::{debug, error, info};
 use std::error::Error;
+use std::ffi::OsStr;
+use std::io::{IsTerminal, Read, Write};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::Ordering;
 use std::vec;
 
+use crate::amend::{append_trailers, git_amend_base, git_head_message};
 use crate::api::common::{ChatFn, ChatMessage, get_chat, get_platform_list};
+use crate::ask::handle_ask;
+use crate::bisect::handle_bisect;
+use crate::branch::handle_branch;
+use crate::bump::handle_version_bump;
+use crate::changelog::handle_changelog;
 use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::explain::{G_EXPLAIN, GitError, explain_enabled, handle_explain_error};
+use crate::filter::filter_diff;
+use crate::history::{git_staged_paths, sample_commit_examples};
+use crate::hook::{HookAction, handle_hook};
+use crate::pr::handle_pr;
+use crate::redact::{G_STRICT, redact_for_ai};
+use crate::resolve::handle_resolve;
+use crate::review::{ReviewFormat, Severity, handle_review};
+use crate::reword::handle_reword;
+use crate::scope::infer_scopes;
+use crate::shape::{DiffArgs, git_shaped, set_diff_args};
+use crate::split::handle_split;
+use crate::squash::handle_squash;
+use crate::summary::handle_summary;
+use crate::ticket::{apply_tickets, branch_tickets};
+use crate::translate::handle_do;
+use crate::why::handle_why;
 
 #[derive(Parser)]
 #[command(version, author, about, long_about = None)]
@@ -14,6 +40,14 @@ struct Cli {
     /// Show supported platforms
     #[arg(short, long)]
     platforms: bool,
+    /// Refuse to send anything to the model when secrets are detected
+    #[arg(long, global = true)]
+    strict: bool,
+    /// Explain failed git commands with the model
+    #[arg(long, global = true)]
+    explain_errors: bool,
+    #[command(flatten)]
+    diff: DiffArgs,
     // subcommand
     #[command(subcommand)]
     command: Option<Commands>,
@@ -43,13 +77,19 @@ enum Commands {
         /// Directly use AI-generated commit message
         #[arg(short, long)]
         direct: bool,
+        /// regenerate the message of HEAD and amend it with the staged changes
+        #[arg(long)]
+        amend: bool,
+        /// amend even if HEAD is already pushed
+        #[arg(short, long, requires = "amend")]
+        force: bool,
     },
     /// List all commits
     List {
         /// number of commits
         #[arg(short, long)]
         number: Option<u32>,
-        /// explain selected commit
+        /// summarize commits with poor messages in one line
         #[arg(short, long)]
         explain: bool,
     },
@@ -61,6 +101,157 @@ enum Commands {
         #[arg(short, long)]
         explain: bool,
     },
+    /// Review the changes with AI
+    Review {
+        /// commit or range to review, e.g. main..HEAD
+        range: Option<String>,
+        /// review the staged changes
+        #[arg(short, long)]
+        staged: bool,
+        /// exit non-zero when a finding reaches this severity
+        #[arg(long, value_enum)]
+        fail_on: Option<Severity>,
+        /// output format of the findings
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: ReviewFormat,
+    },
+    /// Generate a pull request title and description
+    Pr {
+        /// base branch of the pull request
+        #[arg(short, long)]
+        base: Option<String>,
+        /// write the result to a file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Generate a changelog section between two revisions
+    Changelog {
+        /// revision range, e.g. v0.1.0..v0.2.0 or v0.1.0 (up to HEAD)
+        range: String,
+        /// insert the section into a changelog file in place
+        #[arg(long, value_name = "FILE")]
+        prepend: Option<String>,
+    },
+    /// Recommend the next semantic version since the last release tag
+    VersionBump {
+        /// release tag to compare against, defaults to the last semver tag
+        since: Option<String>,
+        /// update the version in Cargo.toml/package.json of the current directory
+        #[arg(short, long)]
+        write: bool,
+        /// create an annotated tag for the new version
+        #[arg(short, long)]
+        tag: bool,
+    },
+    /// Split the staged change into atomic commits
+    Split {
+        /// apply the proposed plan without reviewing it
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Explain why a line exists from its history
+    Why {
+        /// <file>:<line>
+        location: String,
+        /// maximum number of commits to follow
+        #[arg(short, long)]
+        number: Option<u32>,
+    },
+    /// Ask a question about the repository history
+    Ask {
+        /// question in natural language
+        question: String,
+        /// maximum number of commits to read
+        #[arg(short, long)]
+        number: Option<u32>,
+    },
+    /// Find the commit that introduced a regression with git bisect
+    Bisect {
+        /// last known good revision
+        #[arg(short, long)]
+        good: String,
+        /// first known bad revision
+        #[arg(short, long, default_value = "HEAD")]
+        bad: String,
+        /// test command, exits non-zero on bad commits; without it the commits are ranked by suspicion
+        #[arg(short, long)]
+        run: Option<String>,
+        /// description of the failure
+        #[arg(short, long)]
+        symptom: Option<String>,
+    },
+    /// Resolve merge conflicts with proposals to accept, edit or reject
+    Resolve,
+    /// Regenerate the messages of existing commits
+    Reword {
+        /// commits to reword, <rev> means <rev>..HEAD
+        range: String,
+        /// also rewrite commits already on protected or remote branches
+        #[arg(short, long)]
+        force: bool,
+        /// accept the new messages without reviewing them
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Squash the commits since <base> into one commit
+    Squash {
+        /// branch or revision the commits are based on
+        base: String,
+        /// sign the commit
+        #[arg(short, long)]
+        signoff: bool,
+        /// Directly use AI-generated commit message
+        #[arg(short, long)]
+        direct: bool,
+    },
+    /// Suggest branch names from a description or the staged changes
+    Branch {
+        /// description of the planned work
+        #[arg(conflicts_with = "from_staged")]
+        description: Option<String>,
+        /// name the branch after the staged changes
+        #[arg(long)]
+        from_staged: bool,
+        /// ticket id used in the branch name
+        #[arg(short, long)]
+        ticket: Option<String>,
+        /// create and switch to the first available name
+        #[arg(short, long)]
+        create: bool,
+    },
+    /// Translate a request into git commands and run them
+    Do {
+        /// what you want to do, in natural language
+        request: String,
+        /// only show the commands and their risk
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        /// run commands that change the repository without asking; destructive ones are always confirmed
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Explain a git error read from stdin
+    ExplainError,
+    /// Summarize recent commits as a standup report
+    Summary {
+        /// start of the time window, anything `git log --since` accepts
+        #[arg(long, default_value = "yesterday")]
+        since: String,
+        /// end of the time window
+        #[arg(long)]
+        until: Option<String>,
+        /// author to include, `me` is your user.email; repeat for several authors
+        #[arg(short, long = "author")]
+        authors: Vec<String>,
+        /// repositories to include, defaults to the current one
+        #[arg(short, long, num_args = 1..)]
+        repos: Vec<String>,
+    },
+    /// Manage the prepare-commit-msg hook that fills messages for plain `git commit`
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
 }
 
 fn prompt_diff(diff_content: String) -> Vec<ChatMessage> {
@@ -118,7 +309,12 @@ fn prompt_diff(diff_content: String) -> Vec<ChatMessage> {
     diff_msgs
 }
 
-fn prompt_create_commit_msg(commit_content: String) -> Vec<ChatMessage> {
+pub(crate) fn prompt_create_commit_msg(
+    commit_content: String,
+    examples: Vec<String>,
+    scopes: Vec<String>,
+    tickets: Vec<String>,
+) -> Vec<ChatMessage> {
     let mut commit_msgs: Vec<ChatMessage> = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -153,7 +349,7 @@ Rules:
    - Use bullet points if needed
 
 5. **Footer** (optional):
-   - Reference issues (e.g., 'Closes #123')
+   - Reference issues only when they are given to you below, never invent issue numbers
    - BREAKING CHANGE notices if applicable
 
 Analyze this git diff and generate the commit message accordingly. 
@@ -172,19 +368,88 @@ The following is Git patch's description:\n"
         },
     ];
 
+    // scope 由路径映射计算得出，不再让模型猜测
+    if scopes.len() == 1 {
+        commit_msgs[0].content.push_str(format!(
+"
+
+Scope constraint: all changed files belong to the `{}` area. Use exactly `({})` as the scope.",
+            scopes[0], scopes[0]).as_str());
+    } else if scopes.len() > 1 {
+        commit_msgs[0].content.push_str(format!(
+"
+
+Scope constraint: the changed files span several areas: {}. Use `({})` as the scope, and do not invent other scope names.",
+            scopes.iter().map(|s| format!("`{}`", s)).collect::<Vec<String>>().join(", "),
+            scopes.join(",")).as_str());
+    }
+
+    // ticket 由 aigit 写入，模型只需要知道它的存在
+    if !tickets.is_empty() {
+        commit_msgs[0].content.push_str(format!(
+"
+
+Ticket: this work belongs to {}. The ticket reference is added automatically, so do not write it yourself and do not add any other issue references.",
+            tickets.join(", ")).as_str());
+    } else {
+        commit_msgs[0].content.push_str(
+"
+
+No issue is associated with this change. Do not add issue references such as 'Closes #123'.");
+    }
+
+    if !examples.is_empty() {
+        commit_msgs[0].content.push_str(
+"
+
+The following are recent commit messages from this repository, separated by `---`.
+Follow their habits (scope names, ticket prefixes, level of detail) as long as they do not conflict with the rules above:
+");
+        for example in examples.iter() {
+            commit_msgs[0].content.push_str("---\n");
+            commit_msgs[0].content.push_str(example.as_str());
+            commit_msgs[0].content.push('\n');
+        }
+        commit_msgs[0].content.push_str("---");
+    }
+
     commit_msgs[1].content.push_str(commit_content.as_str());
 
     commit_msgs
 }
 
-fn get_git_res(child: Child) -> Result<String, Box<dyn Error>> {
+fn prompt_summary_line(show_content: String) -> Vec<ChatMessage> {
+    let mut summary_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer. The user will provide the output of `git show` for one commit whose message is not informative.
+Summarize what the commit actually does in ONE line:
+    - Imperative tense ('Add' not 'Added')
+    - ≤72 characters
+    - No ending punctuation, no markdown, no quotes
+Output ONLY the summary line with no additional text."
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: "Please summarize the following commit. \n".to_string(),
+        },
+    ];
+
+    summary_msgs[1].content.push_str(show_content.as_str());
+    summary_msgs
+}
+
+pub(crate) fn get_git_res(child: Child) -> Result<String, Box<dyn Error>> {
     let output = child.wait_with_output()?;
 
     match output.status.code() {
         Some(0) => {
             return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
         }
-        Some(128) => {
+        // 128 也用于 bad revision 等致命错误，只有确实不在仓库中时才替换错误信息
+        Some(128) if String::from_utf8_lossy(&output.stderr).contains("not a git repository") => {
             return Err("Not a git repository (or any parent directory)".into());
         }
         _ => {
@@ -193,25 +458,71 @@ fn get_git_res(child: Child) -> Result<String, Box<dyn Error>> {
     }
 }
 
-fn git_diff(index: String, only_staged: bool) -> Result<String, Box<dyn Error>> {
-    let mut args = vec![
-        "diff",       // 显示当前修改
-        "--no-color", // 禁用外部差异工具
-        "-U3",        // 显示3行上下文
-    ];
-
-    if only_staged {
-        args.push("--staged");
-    }
-    args.push(&index.as_str());
-
+// 运行 git 命令并返回标准输出
+pub(crate) fn git_output<S: AsRef<OsStr>>(args: &[S]) -> Result<String, Box<dyn Error>> {
     let child = Command::new("git")
         .args(args)
         .stdout(Stdio::piped()) // 捕获标准输出
         .stderr(Stdio::piped()) // 捕获错误输出
         .spawn()?; // 异步启动
 
-    return get_git_res(child);
+    return get_git_res(child).map_err(|e| GitError::wrap(args, e));
+}
+
+// 前台运行 git 命令，输出直接显示给用户。需要解释错误时同时保留 stderr，
+// 失败时返回带命令的 GitError，没有 stderr 时使用 failure 作为错误信息
+pub(crate) fn git_foreground<S: AsRef<OsStr>>(args: &[S], failure: &str) -> Result<(), Box<dyn Error>> {
+    let capture = explain_enabled();
+    let mut child = Command::new("git")
+        .args(args)
+        .stderr(if capture { Stdio::piped() } else { Stdio::inherit() })
+        .spawn()?; // 前台启动
+
+    let mut stderr: Vec<u8> = vec![];
+    if let Some(mut pipe) = child.stderr.take() {
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = pipe.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            std::io::stderr().write_all(&buf[..n])?;
+            stderr.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    if child.wait()?.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+    Err(GitError::wrap(args, if stderr.is_empty() { failure.into() } else { stderr.into() }))
+}
+
+// 发送给模型前的预处理：过滤 .aigitignore 中的文件，再进行脱敏
+pub(crate) fn prepare_for_ai(content: String) -> Result<String, Box<dyn Error>> {
+    redact_for_ai(filter_diff(content)?)
+}
+
+// 单行的上下文（scope、ticket 等）一起脱敏
+pub(crate) fn prepare_lines_for_ai(lines: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    if lines.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(redact_for_ai(lines.join("\n"))?.lines().map(|line| line.to_string()).collect())
+}
+
+pub(crate) fn git_diff(index: String, only_staged: bool) -> Result<String, Box<dyn Error>> {
+    let mut args = vec![
+        "--no-color".to_string(), // 禁用外部差异工具
+    ];
+
+    if only_staged {
+        args.push("--staged".to_string());
+    }
+    args.push(index);
+
+    // 上下文行数等参数由配置和命令行决定
+    return git_shaped("diff", &args);
 }
 
 fn handle_diff(index: String, explain: bool, staged: bool) -> Result<(), Box<dyn Error>> {
@@ -229,7 +540,7 @@ fn handle_diff(index: String, explain: bool, staged: bool) -> Result<(), Box<dyn
         let diff_explain = chat(
             G_AI_MODEL.clone(),
             G_AI_API_KEY.clone(),
-            prompt_diff(diff_content),
+            prompt_diff(prepare_for_ai(diff_content)?),
         )?;
         println!("{}", diff_explain);
     }
@@ -237,21 +548,14 @@ fn handle_diff(index: String, explain: bool, staged: bool) -> Result<(), Box<dyn
     Ok(())
 }
 
-fn git_show(hash: String) -> Result<String, Box<dyn Error>> {
+pub(crate) fn git_show(hash: String) -> Result<String, Box<dyn Error>> {
     let args = [
-        "show",       // 显示指定index
-        "--no-color", // 禁用外部差异工具
-        "-U3",        // 显示3行上下文
-        hash.as_str(),
+        "--no-color".to_string(), // 禁用外部差异工具
+        hash,
     ];
 
-    let child = Command::new("git")
-        .args(args)
-        .stdout(Stdio::piped()) // 捕获标准输出
-        .stderr(Stdio::piped()) // 捕获错误输出
-        .spawn()?; // 异步启动
-
-    return get_git_res(child);
+    // 上下文行数等参数由配置和命令行决定
+    return git_shaped("show", &args);
 }
 
 fn handle_show(hash: String, explain: bool) -> Result<(), Box<dyn Error>> {
@@ -269,7 +573,7 @@ fn handle_show(hash: String, explain: bool) -> Result<(), Box<dyn Error>> {
         let show_explain = chat(
             G_AI_MODEL.clone(),
             G_AI_API_KEY.clone(),
-            prompt_diff(show_content),
+            prompt_diff(prepare_for_ai(show_content)?),
         )?;
         println!("{}", show_explain);
     }
@@ -277,9 +581,33 @@ fn handle_show(hash: String, explain: bool) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn git_commit(signoff: bool, directly: bool, message: String) -> Result<String, Box<dyn Error>> {
+// 用 git 配置的编辑器修改文本，临时文件位于 .git 目录下
+pub(crate) fn edit_in_editor(text: &str, file_name: &str) -> Result<String, Box<dyn Error>> {
+    let git_dir = git_output(&["rev-parse", "--git-dir"])?;
+    let file = format!("{}/{}", git_dir.trim(), file_name);
+    std::fs::write(&file, text)?;
+
+    let editor = git_output(&["var", "GIT_EDITOR"])?;
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor.trim()))
+        .arg(editor.trim())
+        .arg(&file)
+        .status()?;
+    let edited = std::fs::read_to_string(&file);
+    let _ = std::fs::remove_file(&file);
+    if !status.success() {
+        return Err("The editor exited with an error".into());
+    }
+    Ok(edited?)
+}
+
+pub(crate) fn git_commit(signoff: bool, directly: bool, amend: bool, message: String) -> Result<String, Box<dyn Error>> {
     let mut v_args = vec!["commit"];
     
+    if amend {
+        v_args.push("--amend");
+    }
     if signoff {
         v_args.push("--signoff");
     }
@@ -290,64 +618,179 @@ fn git_commit(signoff: bool, directly: bool, message: String) -> Result<String,
     v_args.push("-m");
     v_args.push(message.as_str());
 
-    let status = Command::new("git")
-        .args(v_args)
-        .status()?; // 前台启动
-
-    if status.success() {
-        Ok("Commit successful".to_string())
-    } else {
-        Err("Commit failed".into())
-    }
+    git_foreground(&v_args, "Commit failed")?;
+    Ok("Commit successful".to_string())
 }
 
-fn handle_commit(explain: bool, signoff: bool, directly: bool) -> Result<(), Box<dyn Error>> {
-    let diff_content = git_diff("HEAD".to_string(), true)?;
+fn handle_commit(explain: bool, signoff: bool, directly: bool, amend: bool, force: bool) -> Result<(), Box<dyn Error>> {
+    // --amend 时对比 HEAD^ 和暂存区，包含已提交的修改和新暂存的修改
+    let base = if amend { git_amend_base(force)? } else { "HEAD".to_string() };
+    let diff_content = git_diff(base.clone(), true)?;
     println!("============================================================================");
     println!("Git commit Content");
     println!("============================================================================");
     println!("{}", diff_content);
 
     let mut cm_msg = String::from("# Please edit commit message");
-    if explain {
+    if explain || amend {
         println!("============================================================================");
         println!("Explaining...");
         let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
         let diff_explain = chat(
             G_AI_MODEL.clone(),
             G_AI_API_KEY.clone(),
-            prompt_diff(diff_content),
+            prompt_diff(prepare_for_ai(diff_content)?),
         )?;
         println!("{}", diff_explain);
 
         println!("============================================================================");
         println!("Generating commit message...\n");
+        let staged_paths = git_staged_paths(base.as_str())?;
+        let examples = sample_commit_examples(&staged_paths)?;
+        let scopes = infer_scopes(&staged_paths);
+        let tickets = branch_tickets()?;
+
+        // 原提交信息作为上下文，trailer 由 aigit 原样补回，不交给模型
+        let mut commit_content = diff_explain;
+        let mut trailers: Vec<String> = vec![];
+        if amend {
+            let (old_body, old_trailers) = git_head_message()?;
+            trailers = old_trailers;
+            commit_content.push_str(format!(
+"
+
+The commit being amended had the following message. Keep the intentional details of it, such as ticket references and the reasons given, when they still apply:
+{}", prepare_for_ai(old_body)?).as_str());
+        }
         cm_msg = chat(
             G_AI_MODEL.clone(),
             G_AI_API_KEY.clone(),
-            prompt_create_commit_msg(diff_explain),
+            prompt_create_commit_msg(
+                commit_content,
+                examples,
+                prepare_lines_for_ai(&scopes)?,
+                prepare_lines_for_ai(&tickets)?,
+            ),
         )?;
-        println!("{}", cm_msg);
 
         // cm_msg 是否是以 ``` 开头？
         if cm_msg.starts_with("```") {
             // String 去掉 开头和末尾 ``` 
             cm_msg = cm_msg.trim_start_matches("```").trim_end_matches("```").to_string();
         }
+        cm_msg = apply_tickets(cm_msg, &tickets);
+        cm_msg = append_trailers(cm_msg, &trailers);
+        println!("{}", cm_msg);
     }
 
-    git_commit(signoff, directly, cm_msg)?;
+    git_commit(signoff, directly, amend, cm_msg)?;
 
     Ok(())
 }
 
-fn handle_list(_number: Option<u32>, _explain: bool) -> Result<(), Box<dyn Error>> {
-    println!("List is not supported yet.");
+// git log 中的一条提交
+struct LogEntry {
+    hash: String,
+    author: String,
+    date: String,
+    subject: String,
+}
+
+fn git_log_list(number: u32) -> Result<Vec<LogEntry>, Box<dyn Error>> {
+    let output = git_output(&[
+        "log".to_string(),
+        "--no-color".to_string(),
+        format!("-n{}", number),
+        "--date=short".to_string(),
+        "--format=%H%x1f%an%x1f%ad%x1f%s%x1e".to_string(), // 字段用 0x1f 分隔，记录用 0x1e 分隔
+    ])?;
+
+    let mut entries: Vec<LogEntry> = vec![];
+    for record in output.split('\x1e') {
+        let fields: Vec<&str> = record.trim().split('\x1f').collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        entries.push(LogEntry {
+            hash: fields[0].to_string(),
+            author: fields[1].to_string(),
+            date: fields[2].to_string(),
+            subject: fields[3].to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+// 信息量不足的提交说明，例如 "fix"、"wip"、"update"
+pub(crate) fn is_poor_message(subject: &str) -> bool {
+    const POOR_WORDS: [&str; 14] = [
+        "fix", "fixes", "fixed", "wip", "update", "updates", "updated", "misc", "tmp", "temp",
+        "test", "changes", "stuff", "minor",
+    ];
+
+    let subject = subject.trim().trim_end_matches('.').to_lowercase();
+    if subject.len() < 10 || subject.split_whitespace().count() < 2 {
+        return true;
+    }
+    subject.split_whitespace().all(|word| POOR_WORDS.contains(&word))
+}
+
+// 从终端读取要解释的提交序号，空行或非法输入表示退出
+fn pick_commit(entries: &[LogEntry]) -> Result<Option<String>, Box<dyn Error>> {
+    print!("Select a commit to explain (1-{}, Enter to quit): ", entries.len());
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    match input.trim().parse::<usize>() {
+        Ok(idx) if idx >= 1 && idx <= entries.len() => Ok(Some(entries[idx - 1].hash.clone())),
+        _ => Ok(None),
+    }
+}
+
+fn handle_list(number: Option<u32>, explain: bool) -> Result<(), Box<dyn Error>> {
+    let entries = git_log_list(number.unwrap_or(10))?;
+    println!("============================================================================");
+    println!("Git Log");
+    println!("============================================================================");
+
+    for (idx, entry) in entries.iter().enumerate() {
+        println!(
+            "{:>3}  {}  {}  {:<16}  {}",
+            idx + 1,
+            &entry.hash[..entry.hash.len().min(8)],
+            entry.date,
+            entry.author,
+            entry.subject
+        );
+
+        // 只为说明不清楚的提交生成一行摘要
+        if explain && is_poor_message(entry.subject.as_str()) {
+            let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+            let summary = chat(
+                G_AI_MODEL.clone(),
+                G_AI_API_KEY.clone(),
+                prompt_summary_line(prepare_for_ai(git_show(entry.hash.clone())?)?),
+            )?;
+            println!("     {:>8}  AI: {}", "", summary.lines().next().unwrap_or("").trim());
+        }
+    }
+
+    // 交互式选择提交，并走与 show -e 相同的解释流程
+    if !entries.is_empty()
+        && std::io::stdin().is_terminal()
+        && let Some(hash) = pick_commit(&entries)?
+    {
+        handle_show(hash, true)?;
+    }
     Ok(())
 }
 
 pub fn handle() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
+    G_STRICT.store(cli.strict, Ordering::Relaxed);
+    G_EXPLAIN.store(cli.explain_errors, Ordering::Relaxed);
+    set_diff_args(cli.diff.clone());
 
     if cli.platforms {
         let pl = get_platform_list();
@@ -363,8 +806,10 @@ pub fn handle() -> Result<(), Box<dyn Error>> {
             explain,
             signoff,
             direct,
+            amend,
+            force,
         }) => {
-            handle_commit(explain, signoff, direct)?;
+            handle_commit(explain, signoff, direct, amend, force)?;
         }
         Some(Commands::List { number, explain }) => {
             handle_list(number, explain)?;
@@ -372,6 +817,54 @@ pub fn handle() -> Result<(), Box<dyn Error>> {
         Some(Commands::Show { hash, explain }) => {
             handle_show(hash.unwrap_or("HEAD".to_string()), explain)?;
         }
+        Some(Commands::Review { range, staged, fail_on, format }) => {
+            handle_review(range, staged, fail_on, format)?;
+        }
+        Some(Commands::Pr { base, output }) => {
+            handle_pr(base, output)?;
+        }
+        Some(Commands::Changelog { range, prepend }) => {
+            handle_changelog(range, prepend)?;
+        }
+        Some(Commands::VersionBump { since, write, tag }) => {
+            handle_version_bump(since, write, tag)?;
+        }
+        Some(Commands::Split { yes }) => {
+            handle_split(yes)?;
+        }
+        Some(Commands::Why { location, number }) => {
+            handle_why(location, number.unwrap_or(10))?;
+        }
+        Some(Commands::Ask { question, number }) => {
+            handle_ask(question, number.unwrap_or(10))?;
+        }
+        Some(Commands::Bisect { good, bad, run, symptom }) => {
+            handle_bisect(good, bad, run, symptom)?;
+        }
+        Some(Commands::Resolve) => {
+            handle_resolve()?;
+        }
+        Some(Commands::Reword { range, force, yes }) => {
+            handle_reword(range, force, yes)?;
+        }
+        Some(Commands::Squash { base, signoff, direct }) => {
+            handle_squash(base, signoff, direct)?;
+        }
+        Some(Commands::Branch { description, from_staged, ticket, create }) => {
+            handle_branch(description, from_staged, ticket, create)?;
+        }
+        Some(Commands::Do { request, dry_run, yes }) => {
+            handle_do(request, dry_run, yes)?;
+        }
+        Some(Commands::ExplainError) => {
+            handle_explain_error()?;
+        }
+        Some(Commands::Summary { since, until, authors, repos }) => {
+            handle_summary(since, until, authors, repos)?;
+        }
+        Some(Commands::Hook { action }) => {
+            handle_hook(action)?;
+        }
         _ => {}
     }
 
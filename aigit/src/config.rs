@@ -1,12 +1,18 @@
-use config::Config;
+use config::builder::DefaultState;
+use config::{Config, ConfigBuilder};
 use lazy_static::lazy_static;
+use std::process::{Command, Stdio};
 
 // 定义配置文件路径
 const CONFIG_TOML_PATH: &str = ".config/aigit/config.toml";
+// 仓库级配置文件，位于仓库根目录，会覆盖用户配置
+const REPO_CONFIG_TOML_PATH: &str = ".aigit.toml";
 
 lazy_static! {
     // 只读全局变量示例
     pub static ref G_CONFIG: Config = init_aigit_config().unwrap();
+    // 不调用模型的命令（diff、show 等）使用的可选配置，配置文件不存在时为空，各项取默认值
+    pub static ref G_OPTIONAL_CONFIG: Config = init_optional_config();
     pub static ref G_AI_PLATFORM: String = G_CONFIG.get_string("platform").expect("Failed to get 'platform' from config.toml");
     pub static ref G_AI_MODEL: String = G_CONFIG.get_string("model").expect("Failed to get 'model' from config.toml");
     pub static ref G_AI_API_KEY: String = G_CONFIG.get_string("api_key").expect("Failed to get 'api_key' from config.toml");
@@ -16,17 +22,112 @@ lazy_static! {
     pub static ref G_AI_API_PORT: String = G_CONFIG.get_string("api_port").expect("Failed to get 'api_port' from config.toml");
     // for API timeout
     pub static ref G_AI_API_TIMEOUT: String = G_CONFIG.get_string("api_timeout").expect("Failed to get 'api_timeout' from config.toml");
+
+    // [history] 历史提交示例，均为可选项
+    pub static ref G_HISTORY_EXAMPLES: usize = G_CONFIG.get_int("history.examples").map(|n| n.max(0) as usize).unwrap_or(5);
+    pub static ref G_HISTORY_AUTHOR: String = G_CONFIG.get_string("history.author").unwrap_or_default();
+    pub static ref G_HISTORY_NO_MERGES: bool = G_CONFIG.get_bool("history.no_merges").unwrap_or(true);
+
+    // 是否从 Cargo/npm workspace 和 CODEOWNERS 自动推断 scope
+    pub static ref G_AUTO_SCOPE: bool = G_CONFIG.get_bool("auto_scope").unwrap_or(true);
+
+    // git 命令失败时是否让模型解释错误
+    pub static ref G_EXPLAIN_ERRORS: bool = G_OPTIONAL_CONFIG.get_bool("explain_errors").unwrap_or(false);
+
+    // [ticket] 从分支名提取 ticket
+    pub static ref G_TICKET_PATTERN: String = G_CONFIG.get_string("ticket.pattern").unwrap_or(String::from(r"[A-Z][A-Z0-9]+-[0-9]+"));
+    // footer、prefix 或 none
+    pub static ref G_TICKET_PLACEMENT: String = G_CONFIG.get_string("ticket.placement").unwrap_or(String::from("footer"));
+    pub static ref G_TICKET_FOOTER: String = G_CONFIG.get_string("ticket.footer").unwrap_or(String::from("Refs"));
+
+    // [diff] 发送给模型的 diff 格式
+    pub static ref G_DIFF_CONTEXT: String = G_OPTIONAL_CONFIG.get_string("diff.context").unwrap_or(String::from("3"));
+    pub static ref G_DIFF_FUNCTION_CONTEXT: bool = G_OPTIONAL_CONFIG.get_bool("diff.function_context").unwrap_or(false);
+    pub static ref G_DIFF_IGNORE_WHITESPACE: bool = G_OPTIONAL_CONFIG.get_bool("diff.ignore_whitespace").unwrap_or(false);
+    pub static ref G_DIFF_FIND_RENAMES: u32 = G_OPTIONAL_CONFIG.get_int("diff.find_renames").map(|n| n.max(0) as u32).unwrap_or(50);
+    pub static ref G_DIFF_FIND_COPIES: u32 = G_OPTIONAL_CONFIG.get_int("diff.find_copies").map(|n| n.max(0) as u32).unwrap_or(0);
+    pub static ref G_DIFF_ALGORITHM: String = G_OPTIONAL_CONFIG.get_string("diff.algorithm").unwrap_or(String::from("default"));
+    pub static ref G_DIFF_TOKEN_BUDGET: usize = G_OPTIONAL_CONFIG.get_int("diff.token_budget").map(|n| n.max(0) as usize).unwrap_or(8000);
+
+    // [ignore] 是否使用内置的 .aigitignore 默认规则
+    pub static ref G_IGNORE_DEFAULTS: bool = G_CONFIG.get_bool("ignore.defaults").unwrap_or(true);
+
+    // [review] 达到该严重程度时 review 返回非零，none 表示不检查
+    pub static ref G_REVIEW_FAIL_ON: String = G_CONFIG.get_string("review.fail_on").unwrap_or(String::from("high"));
+
+    // [pr] 默认的目标分支
+    pub static ref G_PR_BASE: String = G_CONFIG.get_string("pr.base").unwrap_or(String::from("main"));
+
+    // [branch] 生成分支名的格式，没有 ticket 时去掉 <ticket> 和它后面的分隔符
+    pub static ref G_BRANCH_PATTERN: String = G_CONFIG.get_string("branch.pattern").unwrap_or(String::from("<type>/<ticket>-<slug>"));
+
+    // [hook] prepare-commit-msg 钩子等待模型的最长秒数，超时后留空提交信息
+    pub static ref G_HOOK_TIMEOUT: u64 = G_CONFIG.get_int("hook.timeout").map(|n| n.max(1) as u64).unwrap_or(20);
+
+    // [reword] 不允许改写的分支，已在这些分支或远程分支上的提交需要 --force 才能改写
+    pub static ref G_REWORD_PROTECTED: Vec<String> = G_CONFIG
+        .get_array("reword.protected")
+        .map(|v| v.into_iter().filter_map(|v| v.into_string().ok()).collect())
+        .unwrap_or(vec![String::from("main"), String::from("master")]);
+
+    // [redact] 发送给模型前的脱敏
+    pub static ref G_REDACT_ENABLED: bool = G_CONFIG.get_bool("redact.enabled").unwrap_or(true);
+    pub static ref G_REDACT_STRICT: bool = G_CONFIG.get_bool("redact.strict").unwrap_or(false);
+    pub static ref G_REDACT_EMAILS: bool = G_CONFIG.get_bool("redact.emails").unwrap_or(true);
+    pub static ref G_REDACT_ENTROPY: f64 = G_CONFIG.get_float("redact.entropy").unwrap_or(4.0);
+    pub static ref G_REDACT_PATTERNS: Vec<String> = G_CONFIG
+        .get_array("redact.patterns")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|v| v.into_string().ok())
+        .collect();
 }
 
-// 读取 ${HOME}/.config/aigit/config.toml 配置文件
-fn init_aigit_config() -> Result<Config, Box<dyn std::error::Error>>  {
+// 获取当前 git 仓库的根目录
+pub fn repo_root() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// ${HOME}/.config/aigit/config.toml 配置文件，以及仓库根目录下可选的 .aigit.toml
+fn config_builder(required: bool) -> Result<ConfigBuilder<DefaultState>, Box<dyn std::error::Error>> {
     // 读取环境变量 HOME
     let home_dir = std::env::var("HOME")?;
     let config_path = format!("{}/{}", home_dir, CONFIG_TOML_PATH);
 
-    let config: Config = config::Config::builder()
-        .add_source(config::File::with_name(config_path.as_str()))
+    let mut builder = config::Config::builder()
+        .add_source(config::File::with_name(config_path.as_str()).required(required));
+    if let Some(root) = repo_root() {
+        let repo_config_path = format!("{}/{}", root, REPO_CONFIG_TOML_PATH);
+        builder = builder.add_source(
+            config::File::new(repo_config_path.as_str(), config::FileFormat::Toml).required(false),
+        );
+    }
+    Ok(builder)
+}
+
+fn init_aigit_config() -> Result<Config, Box<dyn std::error::Error>>  {
+    let config: Config = config_builder(true)?
         .build().expect("Failed to load ${HOME}/.config/aigit/config.toml");
 
     return Ok(config);
-}
\ No newline at end of file
+}
+
+fn init_optional_config() -> Config {
+    match config_builder(false).and_then(|builder| Ok(builder.build()?)) {
+        Ok(config) => config,
+        Err(e) => {
+            log::debug!("optional config not loaded: {}", e);
+            Config::default()
+        }
+    }
+}
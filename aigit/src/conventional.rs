@@ -0,0 +1,68 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use std::error::Error;
+
+use crate::command::git_output;
+
+// 范围内的一条提交，已按 conventional commits 解析
+#[derive(Debug, Clone)]
+pub struct RangeCommit {
+    pub hash: String,
+    pub subject: String,
+    pub body: String,
+    // 不符合 conventional commits 格式时为 None
+    pub kind: Option<String>,
+    pub scope: Option<String>,
+    pub description: String,
+    pub breaking: bool,
+}
+
+// 解析 `<type>(<scope>)!: <description>` 以及 BREAKING CHANGE footer
+pub fn parse_conventional(hash: &str, subject: &str, body: &str) -> Result<RangeCommit, Box<dyn Error>> {
+    let re = Regex::new(r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^)]*)\))?(?P<bang>!)?:\s*(?P<desc>.+)$")?;
+    let footer_re = Regex::new(r"(?m)^BREAKING[ -]CHANGE:")?;
+
+    let mut commit = RangeCommit {
+        hash: hash.to_string(),
+        subject: subject.to_string(),
+        body: body.to_string(),
+        kind: None,
+        scope: None,
+        description: subject.to_string(),
+        breaking: footer_re.is_match(body),
+    };
+
+    if let Some(caps) = re.captures(subject.trim()) {
+        commit.kind = caps.name("type").map(|m| m.as_str().to_lowercase());
+        commit.scope = caps
+            .name("scope")
+            .map(|m| m.as_str().trim().to_string())
+            .filter(|s| !s.is_empty());
+        commit.description = caps.name("desc").map(|m| m.as_str().trim().to_string()).unwrap_or_default();
+        commit.breaking |= caps.name("bang").is_some();
+    }
+    Ok(commit)
+}
+
+// 读取 <range> 中的提交（不含 merge），从旧到新
+pub fn git_range_commits(range: &str) -> Result<Vec<RangeCommit>, Box<dyn Error>> {
+    let output = git_output(&[
+        "log",
+        "--no-color",
+        "--no-merges",
+        "--reverse",
+        "--format=%H%x1f%s%x1f%b%x1e", // 字段用 0x1f 分隔，记录用 0x1e 分隔
+        range,
+    ])?;
+
+    let mut commits: Vec<RangeCommit> = vec![];
+    for record in output.split('\x1e') {
+        let fields: Vec<&str> = record.trim_start().split('\x1f').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+        commits.push(parse_conventional(fields[0], fields[1], fields[2].trim())?);
+    }
+    Ok(commits)
+}
@@ -0,0 +1,125 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_EXPLAIN_ERRORS};
+
+// 命令行 --explain-errors 开关
+pub static G_EXPLAIN: AtomicBool = AtomicBool::new(false);
+
+// git status 快照最多保留的行数
+const STATUS_LINES: usize = 30;
+
+// 执行失败的 git 命令，Display 和 Debug 只输出 stderr，保持原有的错误信息
+pub struct GitError {
+    pub command: String,
+    pub stderr: String,
+}
+
+impl GitError {
+    pub fn wrap<S: AsRef<OsStr>>(args: &[S], err: Box<dyn Error>) -> Box<dyn Error> {
+        let mut command = String::from("git");
+        for arg in args.iter() {
+            command.push(' ');
+            command.push_str(arg.as_ref().to_string_lossy().as_ref());
+        }
+        Box::new(GitError { command, stderr: err.to_string() })
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.stderr)
+    }
+}
+
+impl fmt::Debug for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.stderr)
+    }
+}
+
+impl Error for GitError {}
+
+fn prompt_explain_error(failure: String, status: String) -> Vec<ChatMessage> {
+    let mut explain_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a Git expert helping a developer who hit an error. The user will provide the failed git command (when known), the error output and a snapshot of `git status`.
+
+### Output Specification:
+    - Plain text, no markdown headings
+    - First explain in 1-3 sentences what went wrong and why, in plain words
+    - Then list the commands that fix it under `Suggested fix:`, one per line, each followed by a short comment after `#`
+    - Prefer safe commands; warn explicitly before any command that discards work or rewrites history
+    - Do not speculate about context not present in the input"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    explain_msgs[1].content.push_str(failure.as_str());
+    explain_msgs[1].content.push_str("\n### git status\n");
+    explain_msgs[1].content.push_str(status.as_str());
+    explain_msgs
+}
+
+// 失败时的仓库状态，包含分支、进行中的 merge/rebase 和修改的文件
+fn git_status_snapshot() -> String {
+    match git_output(&["status", "--branch", "--short"]) {
+        Ok(status) => status.lines().take(STATUS_LINES).collect::<Vec<&str>>().join("\n"),
+        Err(e) => format!("(git status failed: {})", e),
+    }
+}
+
+fn ask_explanation(failure: String) -> Result<String, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_explain_error(prepare_for_ai(failure)?, prepare_for_ai(git_status_snapshot())?),
+    )
+}
+
+// 命令行开关优先，其次是配置文件；配置只在需要时读取，读取失败时不解释
+pub fn explain_enabled() -> bool {
+    if G_EXPLAIN.load(Ordering::Relaxed) {
+        return true;
+    }
+    *G_EXPLAIN_ERRORS
+}
+
+// 解释 aigit 执行中失败的 git 命令，输出到 stderr，不影响 stdout 上的结果
+pub fn explain_failure(err: &(dyn Error + 'static)) -> Result<(), Box<dyn Error>> {
+    let Some(git_err) = err.downcast_ref::<GitError>() else {
+        debug!("not a git failure, nothing to explain");
+        return Ok(());
+    };
+
+    eprintln!("Explaining the git error...\n");
+    let failure = format!("### Command\n{}\n\n### Error output\n{}\n", git_err.command, git_err.stderr);
+    eprintln!("{}", ask_explanation(failure)?);
+    Ok(())
+}
+
+pub fn handle_explain_error() -> Result<(), Box<dyn Error>> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    if input.trim().is_empty() {
+        return Err("Nothing to explain, pipe the error output to stdin, e.g. `git push 2>&1 | aigit explain-error`".into());
+    }
+
+    let explanation = ask_explanation(format!("### Error output\n{}\n", input))?;
+    println!("{}", explanation);
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::path::Path;
+
+use crate::config::{G_IGNORE_DEFAULTS, repo_root};
+
+// 仓库根目录下的忽略文件，语法与 .gitignore 相同
+const AIGITIGNORE_PATH: &str = ".aigitignore";
+
+// 内置的默认规则：锁文件、第三方代码、快照和生成代码
+const DEFAULT_IGNORES: [&str; 24] = [
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "vendor/",
+    "third_party/",
+    "node_modules/",
+    "__snapshots__/",
+    "*.snap",
+    "*.pb.go",
+    "*.pb.cc",
+    "*.pb.h",
+    "*_pb2.py",
+    "*_pb2_grpc.py",
+    "*.min.js",
+    "*.min.css",
+];
+
+fn build_matcher() -> Result<Gitignore, Box<dyn Error>> {
+    let root = repo_root().unwrap_or(String::from("."));
+    let mut builder = GitignoreBuilder::new(root.as_str());
+
+    if *G_IGNORE_DEFAULTS {
+        for pattern in DEFAULT_IGNORES.iter() {
+            builder.add_line(None, pattern)?;
+        }
+    }
+
+    // .aigitignore 放在默认规则之后，可以用 `!` 重新包含默认忽略的文件
+    let aigitignore = Path::new(root.as_str()).join(AIGITIGNORE_PATH);
+    if aigitignore.exists()
+        && let Some(e) = builder.add(aigitignore)
+    {
+        return Err(e.into());
+    }
+
+    Ok(builder.build()?)
+}
+
+// 从 `diff --git a/<path> b/<path>` 中取出新文件路径
+fn section_path(header: &str) -> String {
+    let paths = header.trim_start_matches("diff --git ");
+    match paths.rsplit_once(" b/") {
+        Some((_, path)) => path.trim().to_string(),
+        None => paths.trim_start_matches("a/").trim().to_string(),
+    }
+}
+
+// 统计一个文件片段中增删的行数
+fn changed_lines(section: &[&str]) -> usize {
+    section
+        .iter()
+        .filter(|line| {
+            (line.starts_with('+') && !line.starts_with("+++"))
+                || (line.starts_with('-') && !line.starts_with("---"))
+        })
+        .count()
+}
+
+// 被忽略的路径，用于没有 `diff --git` 文件头、不能交给 filter_diff 的内容
+pub fn ignored_paths(paths: &[&str]) -> Result<Vec<String>, Box<dyn Error>> {
+    let matcher = build_matcher()?;
+    Ok(paths
+        .iter()
+        .filter(|path| matcher.matched_path_or_any_parents(path, false).is_ignore())
+        .map(|path| path.to_string())
+        .collect())
+}
+
+// 过滤 diff 内容，被忽略的文件只保留一行统计信息
+pub fn filter_diff(content: String) -> Result<String, Box<dyn Error>> {
+    let matcher = build_matcher()?;
+    if matcher.is_empty() {
+        return Ok(content);
+    }
+
+    // 按 `diff --git` 切分，第一段是 git show 的提交信息等前导内容
+    let mut sections: Vec<Vec<&str>> = vec![vec![]];
+    for line in content.lines() {
+        if line.starts_with("diff --git ") {
+            sections.push(vec![]);
+        }
+        if let Some(section) = sections.last_mut() {
+            section.push(line);
+        }
+    }
+
+    let mut result: Vec<String> = vec![];
+    let mut omitted: Vec<String> = vec![];
+    for section in sections.iter() {
+        let Some(header) = section.first().filter(|line| line.starts_with("diff --git ")) else {
+            result.extend(section.iter().map(|line| line.to_string()));
+            continue;
+        };
+
+        let path = section_path(header);
+        if matcher
+            .matched_path_or_any_parents(path.as_str(), false)
+            .is_ignore()
+        {
+            omitted.push(format!("{}: {} lines changed, omitted", path, changed_lines(section)));
+        } else {
+            result.extend(section.iter().map(|line| line.to_string()));
+        }
+    }
+
+    if omitted.is_empty() {
+        return Ok(content);
+    }
+    debug!("omitted from AI context: {:?}", omitted);
+
+    result.push(String::new());
+    result.push(String::from("Files omitted from this diff:"));
+    result.extend(omitted);
+    Ok(result.join("\n"))
+}
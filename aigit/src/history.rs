@@ -0,0 +1,93 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+
+use crate::command::git_output;
+use crate::config::{G_HISTORY_AUTHOR, G_HISTORY_EXAMPLES, G_HISTORY_NO_MERGES};
+use crate::redact::redact_for_ai;
+
+// 脱敏时分隔各条示例
+const EXAMPLE_SEPARATOR: &str = "\n\u{1e}\n";
+
+// 获取暂存区相对 base 被修改的文件路径
+pub fn git_staged_paths(base: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let output = git_output(&["diff", "--staged", "--name-only", base])?;
+    Ok(output
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+// 读取最近的 commit message，paths 非空时只查找修改过这些路径的提交
+fn git_log_messages(number: usize, paths: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let number_arg = format!("-n{}", number);
+    let mut args: Vec<String> = vec![
+        "log".to_string(),
+        "--no-color".to_string(),
+        number_arg,
+        "--format=%B%x00".to_string(), // 以 NUL 分隔每条 message
+    ];
+
+    if *G_HISTORY_NO_MERGES {
+        args.push("--no-merges".to_string());
+    }
+    if !G_HISTORY_AUTHOR.is_empty() {
+        args.push(format!("--author={}", G_HISTORY_AUTHOR.as_str()));
+    }
+    if !paths.is_empty() {
+        // 路径相对于仓库根目录，从子目录运行时也要匹配
+        args.push("--".to_string());
+        args.extend(paths.iter().map(|p| format!(":(top,literal){}", p)));
+    }
+
+    let output = git_output(&args)?;
+    Ok(output
+        .split('\0')
+        .map(|msg| msg.trim().to_string())
+        .filter(|msg| !msg.is_empty())
+        .collect())
+}
+
+// 采样历史 commit message 作为 few-shot 示例。
+// 优先选择修改过相同路径的提交，不足时再用最近的提交补齐。
+// 示例会发送给模型，和 diff 一样先脱敏
+pub fn sample_commit_examples(paths: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let number = *G_HISTORY_EXAMPLES;
+    if number == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut examples: Vec<String> = vec![];
+    if !paths.is_empty() {
+        match git_log_messages(number, paths) {
+            Ok(msgs) => examples = msgs,
+            Err(e) => debug!("Fail to read history for staged paths: {}", e),
+        }
+    }
+
+    if examples.len() < number {
+        match git_log_messages(number, &[]) {
+            Ok(msgs) => {
+                for msg in msgs {
+                    if examples.len() >= number {
+                        break;
+                    }
+                    if !examples.contains(&msg) {
+                        examples.push(msg);
+                    }
+                }
+            }
+            // 新仓库还没有任何提交时 git log 会失败
+            Err(e) => debug!("Fail to read history: {}", e),
+        }
+    }
+
+    debug!("history examples: {}", examples.len());
+    if examples.is_empty() {
+        return Ok(examples);
+    }
+    // 合在一起脱敏，只报告一次
+    let redacted = redact_for_ai(examples.join(EXAMPLE_SEPARATOR))?;
+    Ok(redacted.split(EXAMPLE_SEPARATOR).map(|msg| msg.to_string()).collect())
+}
@@ -0,0 +1,234 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use clap::Subcommand;
+use std::error::Error;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::api::common::{ChatFn, get_chat};
+use crate::command::{git_diff, git_output, prepare_for_ai, prepare_lines_for_ai, prompt_create_commit_msg};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_HOOK_TIMEOUT};
+use crate::history::{git_staged_paths, sample_commit_examples};
+use crate::scope::infer_scopes;
+use crate::ticket::{apply_tickets, branch_tickets};
+
+const HOOK_NAME: &str = "prepare-commit-msg";
+// 安装前已有的钩子改名保存，由 aigit 的钩子先调用
+const CHAINED_HOOK_NAME: &str = "prepare-commit-msg.aigit-chained";
+// 用于识别 aigit 安装的钩子
+const HOOK_MARKER: &str = "# installed by aigit";
+
+#[derive(Subcommand, Debug)]
+pub enum HookAction {
+    /// Install the prepare-commit-msg hook
+    Install,
+    /// Remove the hook and restore the previous one
+    Uninstall,
+    /// Fill the commit message, called by the hook
+    Run {
+        /// file holding the commit message
+        file: String,
+        /// source of the message: message, template, merge, squash or commit
+        source: Option<String>,
+        /// commit object name, given with the commit source
+        sha: Option<String>,
+    },
+}
+
+// 钩子目录，已考虑 core.hooksPath
+fn git_hooks_dir() -> Result<String, Box<dyn Error>> {
+    let dir = git_output(&["rev-parse", "--path-format=absolute", "--git-path", "hooks"])?;
+    Ok(dir.trim().to_string())
+}
+
+fn hook_script() -> Result<String, Box<dyn Error>> {
+    // 使用绝对路径，IDE 中执行 git commit 时 PATH 可能不包含 aigit
+    let exe = std::env::current_exe()?;
+    let exe = exe.to_string_lossy().replace('\'', "'\\''");
+    Ok(format!(
+        "#!/bin/sh
+{}
+# Fills the commit message with aigit, remove with `aigit hook uninstall`.
+
+chained=\"$(dirname \"$0\")/{}\"
+if [ -x \"$chained\" ]; then
+    \"$chained\" \"$@\" || exit $?
+fi
+
+'{}' hook run \"$@\" || true
+exit 0
+",
+        HOOK_MARKER, CHAINED_HOOK_NAME, exe
+    ))
+}
+
+fn is_aigit_hook(path: &str) -> bool {
+    std::fs::read_to_string(path).map(|c| c.contains(HOOK_MARKER)).unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &str) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_mode(0o755);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &str) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+fn install_hook() -> Result<(), Box<dyn Error>> {
+    let dir = git_hooks_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let hook = format!("{}/{}", dir, HOOK_NAME);
+    let chained = format!("{}/{}", dir, CHAINED_HOOK_NAME);
+
+    if std::path::Path::new(&hook).exists() && !is_aigit_hook(hook.as_str()) {
+        if std::path::Path::new(&chained).exists() {
+            return Err(format!("{} already exists, cannot keep the current hook", chained).into());
+        }
+        std::fs::rename(&hook, &chained)?;
+        println!("Existing hook moved to {}, it still runs before aigit", chained);
+    }
+
+    std::fs::write(&hook, hook_script()?)?;
+    make_executable(hook.as_str())?;
+    println!("Installed {}", hook);
+    Ok(())
+}
+
+fn uninstall_hook() -> Result<(), Box<dyn Error>> {
+    let dir = git_hooks_dir()?;
+    let hook = format!("{}/{}", dir, HOOK_NAME);
+    let chained = format!("{}/{}", dir, CHAINED_HOOK_NAME);
+
+    if !std::path::Path::new(&hook).exists() {
+        println!("No {} hook installed.", HOOK_NAME);
+        return Ok(());
+    }
+    if !is_aigit_hook(hook.as_str()) {
+        return Err(format!("{} was not installed by aigit, leaving it untouched", hook).into());
+    }
+
+    std::fs::remove_file(&hook)?;
+    println!("Removed {}", hook);
+    if std::path::Path::new(&chained).exists() {
+        std::fs::rename(&chained, &hook)?;
+        println!("Restored the previous hook");
+    }
+    Ok(())
+}
+
+// 注释字符，core.commentChar 为 auto 或未设置时使用 #
+fn git_comment_char() -> String {
+    let configured = git_output(&["config", "core.commentChar"]).unwrap_or_default();
+    match configured.trim() {
+        "" | "auto" => String::from("#"),
+        comment => comment.to_string(),
+    }
+}
+
+// 注释之外已经有内容时不覆盖，例如 commit.template 或其他钩子写入的内容。
+// `git commit -v` 在剪刀线下面附加 diff，不算作提交信息
+fn has_message(content: &str, comment: &str) -> bool {
+    content
+        .lines()
+        .take_while(|line| !(line.starts_with(comment) && line.contains(" >8 ")))
+        .any(|line| !line.trim().is_empty() && !line.starts_with(comment))
+}
+
+// 暂存区相对 HEAD 的修改，首次提交时和空 tree 比较
+fn staged_base() -> Result<String, Box<dyn Error>> {
+    if git_output(&["rev-parse", "-q", "--verify", "HEAD"]).is_ok() {
+        return Ok(String::from("HEAD"));
+    }
+    Ok(git_output(&["hash-object", "-t", "tree", "/dev/null"])?.trim().to_string())
+}
+
+fn generate_message() -> Result<String, Box<dyn Error>> {
+    let base = staged_base()?;
+    let diff_content = git_diff(base.clone(), true)?;
+    if diff_content.trim().is_empty() {
+        return Err("nothing staged".into());
+    }
+
+    let staged_paths = git_staged_paths(base.as_str())?;
+    let examples = sample_commit_examples(&staged_paths)?;
+    let scopes = infer_scopes(&staged_paths);
+    let tickets = branch_tickets()?;
+    let prompt = prompt_create_commit_msg(
+        prepare_for_ai(diff_content)?,
+        examples,
+        prepare_lines_for_ai(&scopes)?,
+        prepare_lines_for_ai(&tickets)?,
+    );
+
+    // 模型调用放到单独的线程，超时后不再等待
+    let (tx, rx) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+        let answer = chat(G_AI_MODEL.clone(), G_AI_API_KEY.clone(), prompt).map_err(|e| e.to_string());
+        let _ = tx.send(answer);
+    });
+    let mut message = match rx.recv_timeout(Duration::from_secs(*G_HOOK_TIMEOUT)) {
+        Ok(answer) => answer?,
+        Err(RecvTimeoutError::Timeout) => {
+            return Err(format!("the model did not answer within {}s", *G_HOOK_TIMEOUT).into());
+        }
+        // 线程在发送结果前退出，例如读取配置时 panic，报告 panic 的原因
+        Err(RecvTimeoutError::Disconnected) => {
+            let cause = match handle.join() {
+                Err(panic) => panic
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or(panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or(String::from("unknown panic")),
+                Ok(()) => String::from("no answer"),
+            };
+            return Err(format!("the model request failed: {}", cause).into());
+        }
+    };
+
+    if message.starts_with("```") {
+        message = message.trim_start_matches("```").trim_end_matches("```").to_string();
+    }
+    Ok(apply_tickets(message.trim().to_string(), &tickets))
+}
+
+fn run_hook(file: String, source: Option<String>) -> Result<(), Box<dyn Error>> {
+    // message: -m/-F 已给出信息；merge、squash 和 commit（--amend、-c、-C）由 git 准备好了信息
+    match source.as_deref() {
+        None | Some("template") => {}
+        Some(source) => {
+            debug!("skip {} commit", source);
+            return Ok(());
+        }
+    }
+
+    let content = std::fs::read_to_string(&file).unwrap_or_default();
+    if source.is_none() && has_message(content.as_str(), git_comment_char().as_str()) {
+        return Ok(());
+    }
+
+    eprintln!("aigit: generating commit message...");
+    match generate_message() {
+        Ok(message) => {
+            // 生成的信息写在最前面，保留 git 的注释和模板内容
+            std::fs::write(&file, format!("{}\n\n{}", message, content))?;
+        }
+        // 任何失败都不能阻止提交，留给用户自己填写
+        Err(e) => eprintln!("aigit: {}, write the commit message yourself", e),
+    }
+    Ok(())
+}
+
+pub fn handle_hook(action: HookAction) -> Result<(), Box<dyn Error>> {
+    match action {
+        HookAction::Install => install_hook(),
+        HookAction::Uninstall => uninstall_hook(),
+        HookAction::Run { file, source, sha: _ } => run_hook(file, source),
+    }
+}
@@ -4,9 +4,37 @@ use std::error::Error;
 use std::sync::atomic::{AtomicBool, Ordering};
 use log::{debug, info, error};
 
+mod amend;
+// 原有模块的代码风格使用显式 return
+#[allow(clippy::needless_return, clippy::redundant_field_names)]
 mod api;
+mod ask;
+mod bisect;
+mod branch;
+#[allow(clippy::needless_return)]
 mod config;
+mod bump;
+mod changelog;
+#[allow(clippy::needless_return)]
 mod command;
+mod conventional;
+mod explain;
+mod filter;
+mod history;
+mod hook;
+mod pr;
+mod redact;
+mod resolve;
+mod review;
+mod reword;
+mod scope;
+mod shape;
+mod split;
+mod squash;
+mod summary;
+mod ticket;
+mod translate;
+mod why;
 
 // 创建全局的原子变量
 static G_DEBUG: AtomicBool = AtomicBool::new(false);
@@ -73,8 +101,13 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     let ret = command::handle();
-    if ret.is_err() {
+    if let Err(e) = &ret {
         error!("{:?}", ret);
+        if explain::explain_enabled()
+            && let Err(explain_err) = explain::explain_failure(e.as_ref())
+        {
+            error!("Fail to explain the error: {}", explain_err);
+        }
         exit(1);
     }
     exit(0);
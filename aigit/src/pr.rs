@@ -0,0 +1,141 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::path::Path;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_diff, git_output, prepare_for_ai, prepare_lines_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_PR_BASE, repo_root};
+use crate::ticket::branch_tickets;
+
+// GitHub 支持的 PR 模板位置
+const PR_TEMPLATE_PATHS: [&str; 4] = [
+    ".github/pull_request_template.md",
+    ".github/PULL_REQUEST_TEMPLATE.md",
+    "docs/pull_request_template.md",
+    "pull_request_template.md",
+];
+
+fn prompt_pr(commits: String, diff_content: String, template: Option<String>, tickets: Vec<String>) -> Vec<ChatMessage> {
+    let mut pr_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer writing a pull request. The user will provide the commit list of the branch and the combined Git patch against the base branch.
+
+### Output Specification:
+    1. The first line is the PR title:
+    - Imperative tense ('Add' not 'Added')
+    - ≤72 characters, no ending punctuation
+    2. Then an empty line.
+    3. Then the PR description in Markdown.
+
+Do not speculate about context not present in the commits or the patch.
+Output ONLY the title and the description with no additional text."
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    match template {
+        Some(template) => {
+            pr_msgs[0].content.push_str(
+"
+
+The repository has a pull request template. Fill in every section of the template below, keep its headings and checklists, and remove the template's instruction comments:
+");
+            pr_msgs[0].content.push_str(template.as_str());
+        }
+        None => {
+            pr_msgs[0].content.push_str(
+"
+
+Use the following sections for the description:
+    ## Summary
+    ## Motivation
+    ## Changes
+    (grouped by area, e.g. module or component)
+    ## Testing
+    ## Risks");
+        }
+    }
+
+    if !tickets.is_empty() {
+        pr_msgs[0].content.push_str(format!("\n\nThis branch belongs to ticket {}. Reference it in the description.", tickets.join(", ")).as_str());
+    }
+
+    pr_msgs[1].content.push_str("Please write the pull request for the following branch.\n\n### Commits\n");
+    pr_msgs[1].content.push_str(commits.as_str());
+    pr_msgs[1].content.push_str("\n### Patch\n");
+    pr_msgs[1].content.push_str(diff_content.as_str());
+    pr_msgs
+}
+
+fn read_pr_template() -> Option<String> {
+    let root = repo_root()?;
+    PR_TEMPLATE_PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(Path::new(root.as_str()).join(path)).ok())
+}
+
+pub(crate) fn git_merge_base(base: &str) -> Result<String, Box<dyn Error>> {
+    let output = git_output(&["merge-base", base, "HEAD"])?;
+    Ok(output.trim().to_string())
+}
+
+// 分支上的提交列表（不含 merge）
+fn git_branch_commits(merge_base: &str) -> Result<String, Box<dyn Error>> {
+    git_output(&[
+        "log".to_string(),
+        "--no-color".to_string(),
+        "--no-merges".to_string(),
+        "--reverse".to_string(),
+        "--format=- %h %s%n%w(0,4,4)%b".to_string(), // 提交正文缩进显示
+        format!("{}..HEAD", merge_base),
+    ])
+}
+
+pub fn handle_pr(base: Option<String>, output: Option<String>) -> Result<(), Box<dyn Error>> {
+    let base = base.unwrap_or(G_PR_BASE.clone());
+    let merge_base = git_merge_base(base.as_str())?;
+    let commits = git_branch_commits(merge_base.as_str())?;
+    if commits.trim().is_empty() {
+        return Err(format!("No commits between '{}' and HEAD", base).into());
+    }
+
+    let diff_content = git_diff(format!("{}..HEAD", merge_base), false)?;
+    let template = read_pr_template();
+    let tickets = branch_tickets()?;
+
+    eprintln!("Generating pull request against '{}' ({})...", base, &merge_base[..merge_base.len().min(8)]);
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let pr = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_pr(
+            prepare_for_ai(commits)?,
+            prepare_for_ai(diff_content)?,
+            template,
+            prepare_lines_for_ai(&tickets)?,
+        ),
+    )?;
+    let pr = pr.trim().trim_start_matches("```markdown").trim_matches('`').trim().to_string();
+
+    match output {
+        Some(path) => {
+            std::fs::write(path.as_str(), format!("{}\n", pr))?;
+            println!("Pull request written to {}", path);
+        }
+        None => {
+            println!("============================================================================");
+            println!("Pull Request");
+            println!("============================================================================");
+            println!("{}", pr);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,279 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use std::collections::HashMap;
+use std::error::Error;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::{
+    G_REDACT_EMAILS, G_REDACT_ENABLED, G_REDACT_ENTROPY, G_REDACT_PATTERNS, G_REDACT_STRICT,
+};
+
+// 命令行 --strict 开关
+pub static G_STRICT: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    // 一次运行中的所有输入共用，不同输入中的不同密钥不会得到相同的占位符
+    static ref G_REDACTOR: Mutex<Redactor> = Mutex::new(Redactor::new());
+}
+
+// 高熵字符串的最小长度
+const ENTROPY_MIN_LEN: usize = 24;
+
+// 常见的密钥格式：(名称, 正则)。包含 `secret`、`quoted` 或 `literal` 命名捕获组时只替换该组
+const SECRET_PATTERNS: [(&str, &str); 10] = [
+    ("PRIVATE_KEY", r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----"),
+    ("AWS_ACCESS_KEY", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    ("GITHUB_TOKEN", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b"),
+    ("GITLAB_TOKEN", r"\bglpat-[A-Za-z0-9_\-]{20,}\b"),
+    ("SLACK_TOKEN", r"\bxox[abposr]-[A-Za-z0-9\-]{10,}\b"),
+    ("GOOGLE_API_KEY", r"\bAIza[0-9A-Za-z_\-]{35}\b"),
+    ("OPENAI_API_KEY", r"\bsk-(?:proj-)?[A-Za-z0-9_\-]{20,}\b"),
+    ("JWT", r"\beyJ[A-Za-z0-9_\-]{10,}\.eyJ[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}\b"),
+    ("BEARER_TOKEN", r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9_\-\.=]{16,})"),
+    // 只匹配引号中的字符串或字面量，`token: Option<String>` 等类型标注和变量引用不算
+    ("SECRET_ASSIGNMENT", r#"(?im)(?:password|passwd|secret|token|api[_\-]?key|access[_\-]?key|private[_\-]?key)[A-Za-z0-9_]*["']?\s*[:=]\s*(?:"(?P<secret>[^"\s]{6,})"|'(?P<quoted>[^'\s]{6,})'|(?P<literal>[A-Za-z0-9_\-.+/=@!$%*~]{6,})(?:[\s,;]|$))"#),
+];
+
+// 个人信息，不算作密钥，strict 模式下不拒绝
+const PII_KINDS: [&str; 1] = ["EMAIL"];
+
+const EMAIL_PATTERN: &str = r"\b[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}\b";
+const TOKEN_PATTERN: &str = r"[A-Za-z0-9/_\-][A-Za-z0-9+/_\-]{23,}={0,2}";
+
+// 一次脱敏的结果
+pub struct Redaction {
+    pub content: String,
+    // (占位符, 类型)，同一个值只记录一次
+    pub findings: Vec<(String, String)>,
+}
+
+// 同一个值总是映射到同一个占位符
+struct Redactor {
+    placeholders: HashMap<String, String>,
+    counters: HashMap<String, usize>,
+    findings: Vec<(String, String)>,
+}
+
+impl Redactor {
+    fn new() -> Redactor {
+        Redactor {
+            placeholders: HashMap::new(),
+            counters: HashMap::new(),
+            findings: vec![],
+        }
+    }
+
+    fn placeholder(&mut self, kind: &str, value: &str) -> String {
+        if let Some(placeholder) = self.placeholders.get(value) {
+            return placeholder.clone();
+        }
+
+        let counter = self.counters.entry(kind.to_string()).or_insert(0);
+        *counter += 1;
+        let placeholder = format!("[REDACTED_{}_{}]", kind, counter);
+        self.placeholders.insert(value.to_string(), placeholder.clone());
+        self.findings.push((placeholder.clone(), kind.to_string()));
+        placeholder
+    }
+
+    fn replace(&mut self, content: String, kind: &str, re: &Regex) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut last = 0;
+
+        for caps in re.captures_iter(content.as_str()) {
+            let Some(m) = caps.name("secret").or(caps.name("quoted")).or(caps.name("literal")).or(caps.get(0)) else {
+                continue;
+            };
+            // 不带引号的值要像字面量（包含数字），`= self.token`、`: String` 不是密钥
+            if caps.name("literal").is_some_and(|l| !l.as_str().chars().any(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            // 已经替换过的占位符不再处理
+            if m.as_str().starts_with("[REDACTED_") {
+                continue;
+            }
+            result.push_str(&content[last..m.start()]);
+            result.push_str(self.placeholder(kind, m.as_str()).as_str());
+            last = m.end();
+        }
+        result.push_str(&content[last..]);
+        result
+    }
+
+    fn replace_high_entropy(&mut self, content: String, re: &Regex) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut last = 0;
+
+        for m in re.find_iter(content.as_str()) {
+            if !is_high_entropy(m.as_str()) {
+                continue;
+            }
+            result.push_str(&content[last..m.start()]);
+            result.push_str(self.placeholder("HIGH_ENTROPY", m.as_str()).as_str());
+            last = m.end();
+        }
+        result.push_str(&content[last..]);
+        result
+    }
+}
+
+// 计算字符串的香农熵（bit/字符）
+fn shannon_entropy(s: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in s.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+
+    let len = s.chars().count() as f64;
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// 疑似密钥的随机字符串：足够长、字母数字混合、不是纯十六进制（commit hash 等）
+fn is_high_entropy(token: &str) -> bool {
+    if token.len() < ENTROPY_MIN_LEN || token.starts_with("REDACTED_") {
+        return false;
+    }
+    if token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    if !token.chars().any(|c| c.is_ascii_digit()) || !token.chars().any(|c| c.is_ascii_alphabetic()) {
+        return false;
+    }
+    shannon_entropy(token) >= *G_REDACT_ENTROPY
+}
+
+// 替换内容中的密钥和个人信息，findings 只包含本次新发现的项
+pub fn redact(content: String) -> Result<Redaction, Box<dyn Error>> {
+    let mut redactor = G_REDACTOR.lock().map_err(|e| e.to_string())?;
+    let seen = redactor.findings.len();
+    let mut content = content;
+
+    for (kind, pattern) in SECRET_PATTERNS.iter() {
+        content = redactor.replace(content, kind, &Regex::new(pattern)?);
+    }
+    for pattern in G_REDACT_PATTERNS.iter() {
+        content = redactor.replace(content, "CUSTOM", &Regex::new(pattern)?);
+    }
+    if *G_REDACT_EMAILS {
+        content = redactor.replace(content, "EMAIL", &Regex::new(EMAIL_PATTERN)?);
+    }
+    content = redactor.replace_high_entropy(content, &Regex::new(TOKEN_PATTERN)?);
+
+    Ok(Redaction {
+        content,
+        findings: redactor.findings[seen..].to_vec(),
+    })
+}
+
+// 密钥的数量，邮箱等个人信息只替换不计入
+fn count_secrets(findings: &[(String, String)]) -> usize {
+    findings.iter().filter(|(_, kind)| !PII_KINDS.contains(&kind.as_str())).count()
+}
+
+// 在内容发送给模型之前脱敏并报告，strict 模式下发现密钥直接拒绝
+pub fn redact_for_ai(content: String) -> Result<String, Box<dyn Error>> {
+    if !*G_REDACT_ENABLED {
+        return Ok(content);
+    }
+
+    let redaction = redact(content)?;
+    if redaction.findings.is_empty() {
+        return Ok(redaction.content);
+    }
+
+    // 报告输出到 stderr，避免混入 review 等命令的结构化输出
+    eprintln!("============================================================================");
+    eprintln!("Redacted {} item(s) before sending to the model:", redaction.findings.len());
+    for (placeholder, kind) in redaction.findings.iter() {
+        eprintln!("  {} ({})", placeholder, kind.to_lowercase().replace('_', " "));
+    }
+
+    let secrets = count_secrets(&redaction.findings);
+    if secrets > 0 && (G_STRICT.load(Ordering::Relaxed) || *G_REDACT_STRICT) {
+        return Err(format!("Strict mode: refusing to send content, {} secret(s) detected", secrets).into());
+    }
+
+    Ok(redaction.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 只用内置规则，不读取配置文件
+    fn assignment_findings(content: &str) -> (String, Vec<(String, String)>) {
+        let (kind, pattern) = SECRET_PATTERNS.iter().find(|(kind, _)| *kind == "SECRET_ASSIGNMENT").unwrap();
+        let mut redactor = Redactor::new();
+        let content = redactor.replace(content.to_string(), kind, &Regex::new(pattern).unwrap());
+        (content, redactor.findings)
+    }
+
+    #[test]
+    fn type_annotations_are_not_secrets() {
+        for content in [
+            "    pub static ref G_AI_API_KEY: String = G_CONFIG.get_string(\"api_key\");",
+            "let token: Option<String> = None;",
+            "fn login(user: &str, password: &str, tokens: Vec<String>) {}",
+            "let api_key = self.api_key.clone();",
+            "let token = env::var(\"TOKEN\")?;",
+            "password: &'static str,",
+            "secret = generate_secret();",
+        ] {
+            let (redacted, findings) = assignment_findings(content);
+            assert!(findings.is_empty(), "false positive in {:?}: {:?}", content, findings);
+            assert_eq!(redacted, content);
+        }
+    }
+
+    #[test]
+    fn assigned_literals_are_secrets() {
+        for (content, secret) in [
+            ("api_key = \"abcd1234efgh\"", "abcd1234efgh"),
+            ("\"password\": \"hunter2hunter\",", "hunter2hunter"),
+            ("DB_PASSWORD=s3cr3t-pass", "s3cr3t-pass"),
+            ("token: 'zxcvbnm987'", "zxcvbnm987"),
+            ("client_secret: 9f8e7d6c5b", "9f8e7d6c5b"),
+        ] {
+            let (redacted, findings) = assignment_findings(content);
+            assert_eq!(findings.len(), 1, "missed secret in {:?}", content);
+            assert!(!redacted.contains(secret), "{:?} still contains {:?}", redacted, secret);
+        }
+    }
+
+    #[test]
+    fn emails_do_not_count_as_secrets() {
+        let mut redactor = Redactor::new();
+        let content = redactor.replace(
+            String::from("commit 1a2b3c\nAuthor: Jane Doe <jane@example.com>\n"),
+            "EMAIL",
+            &Regex::new(EMAIL_PATTERN).unwrap(),
+        );
+        assert!(!content.contains("jane@example.com"));
+        assert_eq!(redactor.findings.len(), 1);
+        assert_eq!(count_secrets(&redactor.findings), 0);
+
+        redactor.placeholder("GITHUB_TOKEN", "ghp_0123456789");
+        assert_eq!(count_secrets(&redactor.findings), 1);
+    }
+
+    #[test]
+    fn placeholders_are_unique_across_inputs() {
+        let (kind, pattern) = SECRET_PATTERNS.iter().find(|(kind, _)| *kind == "SECRET_ASSIGNMENT").unwrap();
+        let re = Regex::new(pattern).unwrap();
+        let mut redactor = Redactor::new();
+        let first = redactor.replace(String::from("token = \"first1secret\""), kind, &re);
+        let second = redactor.replace(String::from("token = \"second2secret\""), kind, &re);
+        let again = redactor.replace(String::from("token = \"first1secret\""), kind, &re);
+        assert_ne!(first, second);
+        assert_eq!(first, again);
+    }
+}
@@ -0,0 +1,428 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+use std::io::IsTerminal;
+use std::process::Command;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{edit_in_editor, git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, repo_root};
+use crate::split::read_line;
+
+// 编辑建议时使用的临时文件，位于 .git 目录下
+const RESOLVE_EDIT_PATH: &str = "AIGIT_RESOLVE";
+// 冲突块前后展示给模型的上下文行数
+const BLOCK_CONTEXT_LINES: usize = 5;
+// 每个文件最多读取的双方提交数
+const INTENT_COMMITS: u32 = 5;
+
+// 文件中的一个冲突块，行号为左闭右开区间
+struct ConflictBlock {
+    start: usize,
+    end: usize,
+    ours_label: String,
+    theirs_label: String,
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+}
+
+// 模型对一个冲突块的建议
+#[derive(Debug, Deserialize)]
+struct Proposal {
+    block: usize,
+    resolution: String,
+    #[serde(default)]
+    reasoning: String,
+}
+
+fn prompt_resolve(path: String, intent: String, blocks: String) -> Vec<ChatMessage> {
+    let mut resolve_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer resolving merge conflicts. The user will provide the commits on both sides that touched the file, to show their intent, and the numbered conflict blocks with the lines around them. Each block has the `ours` version, the `theirs` version and, when available, the `base` version both sides started from.
+
+For every block:
+    - `block`: the block number exactly as given
+    - `resolution`: the exact lines that replace the whole block, without conflict markers; keep the changes of both sides whenever they are compatible, and keep the indentation of the surrounding code
+    - `reasoning`: one or two sentences explaining what each side changed and why the resolution is correct
+
+Do not speculate about context not present in the input.
+Output ONLY a JSON array with no additional text and no markdown, for example:
+[{\"block\": 1, \"resolution\": \"let timeout = config.timeout.max(30);\\n\", \"reasoning\": \"Ours renamed the variable, theirs raised the minimum; both changes are kept.\"}]"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    resolve_msgs[1].content.push_str(format!("Please resolve the conflicts in {}.\n", path).as_str());
+    resolve_msgs[1].content.push_str("\n### Intent\n");
+    resolve_msgs[1].content.push_str(intent.as_str());
+    resolve_msgs[1].content.push_str("\n### Conflicts\n");
+    resolve_msgs[1].content.push_str(blocks.as_str());
+    resolve_msgs
+}
+
+fn git_conflicted_files() -> Result<Vec<String>, Box<dyn Error>> {
+    let output = git_output(&["diff", "--name-only", "--diff-filter=U"])?;
+    Ok(output.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+}
+
+// 正在进行的操作和对方的提交：merge、rebase、cherry-pick 或 revert
+fn git_theirs_ref() -> Option<(&'static str, &'static str)> {
+    [
+        ("merge", "MERGE_HEAD"),
+        ("rebase", "REBASE_HEAD"),
+        ("cherry-pick", "CHERRY_PICK_HEAD"),
+        ("revert", "REVERT_HEAD"),
+    ]
+    .into_iter()
+    .find(|(_, head)| git_output(&["rev-parse", "-q", "--verify", head]).is_ok())
+}
+
+// 识别冲突标记，标记后面可以跟标签
+fn marker_label(line: &str, marker: char) -> Option<String> {
+    let text = line.trim_end_matches(['\n', '\r']);
+    let rest = text.strip_prefix(marker.to_string().repeat(7).as_str())?;
+    if rest.is_empty() {
+        return Some(String::new());
+    }
+    rest.strip_prefix(' ').map(|label| label.to_string())
+}
+
+fn parse_conflicts(lines: &[&str]) -> Vec<ConflictBlock> {
+    let mut blocks: Vec<ConflictBlock> = vec![];
+    let mut idx = 0;
+    while idx < lines.len() {
+        let Some(ours_label) = marker_label(lines[idx], '<') else {
+            idx += 1;
+            continue;
+        };
+
+        let start = idx;
+        let mut section = 0;
+        let (mut ours, mut base, mut theirs) = (String::new(), None::<String>, String::new());
+        let mut end = None;
+        let mut theirs_label = String::new();
+        idx += 1;
+        while idx < lines.len() {
+            let line = lines[idx];
+            if section == 0 && marker_label(line, '|').is_some() {
+                section = 1;
+                base = Some(String::new());
+            } else if section < 2 && marker_label(line, '=').is_some_and(|l| l.is_empty()) {
+                section = 2;
+            } else if section == 2 && let Some(label) = marker_label(line, '>') {
+                theirs_label = label;
+                end = Some(idx + 1);
+                break;
+            } else {
+                match section {
+                    0 => ours.push_str(line),
+                    1 => base.get_or_insert_default().push_str(line),
+                    _ => theirs.push_str(line),
+                }
+            }
+            idx += 1;
+        }
+
+        // 不完整的冲突块不处理
+        let Some(end) = end else {
+            break;
+        };
+        blocks.push(ConflictBlock { start, end, ours_label, theirs_label, ours, base, theirs });
+        idx = end;
+    }
+    blocks
+}
+
+// 去掉冲突标记后面的标签，用于比较内容
+fn strip_marker_labels(content: &str) -> String {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            for marker in ['<', '|', '=', '>'] {
+                if marker_label(line, marker).is_some() {
+                    return marker.to_string().repeat(7) + "\n";
+                }
+            }
+            line.to_string()
+        })
+        .collect()
+}
+
+// 冲突块没有 base 时，用暂存区的三个版本重新生成 diff3 格式的内容。
+// 只在文件还没有被手工修改过时使用，避免丢掉用户已经做的修改
+fn diff3_content(path: &str, content: &str, labels: (&str, &str)) -> Result<Option<String>, Box<dyn Error>> {
+    let git_dir = git_output(&["rev-parse", "--git-dir"])?;
+    let mut stage_files: Vec<String> = vec![];
+    for (stage, name) in [(2, "ours"), (1, "base"), (3, "theirs")] {
+        let Ok(blob) = git_output(&["show", format!(":{}:{}", stage, path).as_str()]) else {
+            return Ok(None);
+        };
+        let file = format!("{}/AIGIT_RESOLVE_{}", git_dir.trim(), name.to_uppercase());
+        std::fs::write(&file, blob)?;
+        stage_files.push(file);
+    }
+
+    let merge_file = |style: &str| -> Result<String, Box<dyn Error>> {
+        let mut args = vec!["merge-file", "-p", "-L", labels.0, "-L", "base", "-L", labels.1];
+        if !style.is_empty() {
+            args.push(style);
+        }
+        args.extend(stage_files.iter().map(|f| f.as_str()));
+        // 有冲突时 git merge-file 返回冲突数，不能用 git_output 判断
+        let output = Command::new("git").args(args).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let merged = merge_file("");
+    let diff3 = merge_file("--diff3");
+    for file in stage_files.iter() {
+        let _ = std::fs::remove_file(file);
+    }
+
+    if strip_marker_labels(merged?.as_str()) != strip_marker_labels(content) {
+        debug!("{} was edited after the conflict, keep it as is", path);
+        return Ok(None);
+    }
+    Ok(Some(diff3?))
+}
+
+// 双方修改该文件的提交信息，帮助模型理解意图
+fn git_side_intent(path: &str, theirs: Option<&str>) -> String {
+    // path 相对于仓库根目录，从子目录运行时也要匹配
+    let pathspec = format!(":(top,literal){}", path);
+    let log = |range: &str| -> String {
+        git_output(&[
+            "log",
+            "--no-color",
+            format!("-n{}", INTENT_COMMITS).as_str(),
+            "--format=- %h %s%n%b",
+            range,
+            "--",
+            pathspec.as_str(),
+        ])
+        .unwrap_or_default()
+    };
+
+    let Some(theirs) = theirs else {
+        return format!("Ours (HEAD):\n{}", log("HEAD"));
+    };
+    match git_output(&["merge-base", "HEAD", theirs]) {
+        Ok(base) => format!(
+            "Ours (HEAD):\n{}\nTheirs ({}):\n{}",
+            log(format!("{}..HEAD", base.trim()).as_str()),
+            theirs,
+            log(format!("{}..{}", base.trim(), theirs).as_str())
+        ),
+        Err(_) => format!("Ours (HEAD):\n{}\nTheirs ({}):\n{}", log("HEAD"), theirs, log(theirs)),
+    }
+}
+
+fn describe_blocks(lines: &[&str], blocks: &[ConflictBlock]) -> String {
+    let mut content = String::new();
+    for (idx, block) in blocks.iter().enumerate() {
+        let before = lines[block.start.saturating_sub(BLOCK_CONTEXT_LINES)..block.start].concat();
+        let after = lines[block.end..(block.end + BLOCK_CONTEXT_LINES).min(lines.len())].concat();
+        content.push_str(format!("\n#### Block {} (line {})\n", idx + 1, block.start + 1).as_str());
+        content.push_str(format!("Before:\n{}", before).as_str());
+        content.push_str(format!("Ours ({}):\n{}", block.ours_label, block.ours).as_str());
+        if let Some(base) = &block.base {
+            content.push_str(format!("Base:\n{}", base).as_str());
+        }
+        content.push_str(format!("Theirs ({}):\n{}", block.theirs_label, block.theirs).as_str());
+        content.push_str(format!("After:\n{}", after).as_str());
+    }
+    content
+}
+
+fn ask_proposals(path: &str, intent: String, description: String) -> Result<Vec<Proposal>, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_resolve(path.to_string(), prepare_for_ai(intent)?, prepare_for_ai(description)?),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Err(format!("Fail to find the resolutions in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the resolutions in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+fn print_block(idx: usize, total: usize, block: &ConflictBlock, proposal: Option<&Proposal>) {
+    println!("----------------------------------------------------------------------------");
+    println!("Block {}/{} (line {})", idx + 1, total, block.start + 1);
+    println!("<<<<<<< ours ({})", block.ours_label);
+    print!("{}", block.ours);
+    if let Some(base) = &block.base {
+        println!("||||||| base");
+        print!("{}", base);
+    }
+    println!("=======");
+    print!("{}", block.theirs);
+    println!(">>>>>>> theirs ({})", block.theirs_label);
+    match proposal {
+        Some(proposal) => {
+            println!("\nProposed resolution:");
+            print!("{}", proposal.resolution);
+            if !proposal.resolution.ends_with('\n') {
+                println!();
+            }
+            if !proposal.reasoning.is_empty() {
+                println!("\nReasoning: {}", proposal.reasoning);
+            }
+        }
+        None => println!("\nNo resolution proposed for this block."),
+    }
+}
+
+// 逐块确认，返回每块的处理结果，None 表示保留冲突；用户退出时返回 Ok(None)
+fn review_blocks(blocks: &[ConflictBlock], proposals: &[Proposal]) -> Result<Option<Vec<Option<String>>>, Box<dyn Error>> {
+    let mut resolutions: Vec<Option<String>> = vec![];
+    for (idx, block) in blocks.iter().enumerate() {
+        let proposal = proposals.iter().find(|p| p.block == idx + 1);
+        print_block(idx, blocks.len(), block, proposal);
+        loop {
+            let input = read_line("Commands: a = accept, e = edit, r = reject, q = quit\n> ")?;
+            match (input.as_str(), proposal) {
+                ("a", Some(proposal)) => {
+                    if proposal.resolution.contains("[REDACTED_") {
+                        println!("The resolution contains a redacted placeholder, edit it instead.");
+                        continue;
+                    }
+                    resolutions.push(Some(proposal.resolution.clone()));
+                }
+                ("e", _) => {
+                    let draft = proposal.map(|p| p.resolution.clone()).unwrap_or_else(|| block.ours.clone());
+                    resolutions.push(Some(edit_in_editor(draft.as_str(), RESOLVE_EDIT_PATH)?));
+                }
+                ("r", _) => resolutions.push(None),
+                ("q", _) => return Ok(None),
+                _ => {
+                    println!("Unknown command.");
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+    Ok(Some(resolutions))
+}
+
+// 用处理结果替换冲突块，返回新内容和剩余的冲突块数
+fn apply_resolutions(lines: &[&str], blocks: &[ConflictBlock], resolutions: &[Option<String>]) -> (String, usize) {
+    let mut content = String::new();
+    let mut remaining = 0;
+    let mut pos = 0;
+    for (block, resolution) in blocks.iter().zip(resolutions.iter()) {
+        content.push_str(lines[pos..block.start].concat().as_str());
+        match resolution {
+            Some(text) => {
+                content.push_str(text.as_str());
+                if !text.is_empty() && !text.ends_with('\n') {
+                    content.push('\n');
+                }
+            }
+            None => {
+                content.push_str(lines[block.start..block.end].concat().as_str());
+                remaining += 1;
+            }
+        }
+        pos = block.end;
+    }
+    content.push_str(lines[pos..].concat().as_str());
+    (content, remaining)
+}
+
+// 处理一个文件，返回 false 表示用户退出
+fn resolve_file(root: &str, path: &str, theirs: Option<&str>) -> Result<bool, Box<dyn Error>> {
+    let full_path = format!("{}/{}", root, path);
+    let Ok(mut content) = std::fs::read_to_string(&full_path) else {
+        println!("{}: not a text file in the working tree, resolve it manually.", path);
+        return Ok(true);
+    };
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let blocks = parse_conflicts(&lines);
+    if blocks.is_empty() {
+        println!("{}: no conflict markers found, resolve it manually (e.g. modify/delete).", path);
+        return Ok(true);
+    }
+    let labels = (blocks[0].ours_label.clone(), blocks[0].theirs_label.clone());
+    if blocks.iter().any(|b| b.base.is_none())
+        && let Some(diff3) = diff3_content(path, content.as_str(), (labels.0.as_str(), labels.1.as_str()))?
+    {
+        content = diff3;
+    }
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let blocks = parse_conflicts(&lines);
+
+    println!("============================================================================");
+    println!("{} ({} conflict(s))", path, blocks.len());
+    println!("============================================================================");
+    println!("Resolving...");
+    let intent = git_side_intent(path, theirs);
+    let proposals = ask_proposals(path, intent, describe_blocks(&lines, &blocks))?;
+
+    let Some(resolutions) = review_blocks(&blocks, &proposals)? else {
+        return Ok(false);
+    };
+    let (new_content, remaining) = apply_resolutions(&lines, &blocks, &resolutions);
+    if remaining == blocks.len() {
+        println!("{}: all proposals rejected, the file is untouched.", path);
+        return Ok(true);
+    }
+    std::fs::write(&full_path, new_content)?;
+
+    if remaining == 0 {
+        git_output(&["add", "--", full_path.as_str()])?;
+        println!("{}: resolved and staged.", path);
+    } else {
+        println!("{}: {} conflict(s) left, not staged.", path, remaining);
+    }
+    Ok(true)
+}
+
+pub fn handle_resolve() -> Result<(), Box<dyn Error>> {
+    let files = git_conflicted_files()?;
+    if files.is_empty() {
+        println!("No conflicted files.");
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err("Not running in a terminal, resolve needs to confirm each resolution".into());
+    }
+
+    let root = repo_root().ok_or("Not a git repository (or any parent directory)")?;
+    let theirs = git_theirs_ref();
+    if let Some((operation, head)) = theirs {
+        println!("Resolving {} conflicts against {}", operation, head);
+    }
+
+    for path in files.iter() {
+        if !resolve_file(root.as_str(), path.as_str(), theirs.map(|(_, head)| head))? {
+            println!("Stopped, the current file is untouched.");
+            break;
+        }
+    }
+
+    let left = git_conflicted_files()?;
+    if left.is_empty() {
+        match theirs {
+            Some((operation, _)) => println!("\nAll conflicts resolved, run `git {} --continue` to finish.", operation),
+            None => println!("\nAll conflicts resolved."),
+        }
+    } else {
+        println!("\nStill conflicted: {}", left.join(", "));
+    }
+    Ok(())
+}
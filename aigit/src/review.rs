@@ -0,0 +1,339 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::error::Error;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_diff, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_REVIEW_FAIL_ON};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Severity> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+// review 结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReviewFormat {
+    Text,
+    Json,
+    Sarif,
+    Markdown,
+}
+
+// 模型返回的一条审查意见
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    #[serde(default, deserialize_with = "deserialize_line")]
+    pub line: Option<u32>,
+    #[serde(deserialize_with = "deserialize_severity")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub category: String,
+    pub message: String,
+    #[serde(default)]
+    pub suggestion: String,
+}
+
+// 无法识别的严重程度按 info 处理
+fn deserialize_severity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Severity, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(Severity::parse(value.as_str()).unwrap_or(Severity::Info))
+}
+
+// 行号可能是数字、字符串或 null
+fn deserialize_line<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Number(n) => n.as_u64().map(|n| n as u32),
+        serde_json::Value::String(s) => s.trim().parse::<u32>().ok(),
+        _ => None,
+    })
+}
+
+fn prompt_review(diff_content: String) -> Vec<ChatMessage> {
+    let mut review_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer performing a code review. The user will provide a Git patch (in `git diff` output format).
+
+### Review Requirements:
+    1. Only report real problems introduced or exposed by the added/modified lines.
+    2. For each problem provide:
+    - `file`: path of the file in the new version
+    - `line`: line number in the new version of the file (use the `@@` hunk headers), or null
+    - `severity`: one of `critical`, `high`, `medium`, `low`, `info`
+    - `category`: one of `bug`, `security`, `performance`, `style`
+    - `message`: what is wrong and why it matters
+    - `suggestion`: a concrete fix
+    3. Do not speculate about code that is not present in the patch.
+    4. If there is nothing to report, return an empty array.
+
+### Output Specification:
+    Output ONLY a JSON array with no additional text and no markdown, for example:
+    [{\"file\": \"src/example.py\", \"line\": 18, \"severity\": \"high\", \"category\": \"bug\", \"message\": \"Division by zero when count is 0\", \"suggestion\": \"Return early when count is 0\"}]"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: "Please review the code modification below. \n".to_string(),
+        },
+    ];
+
+    review_msgs[1].content.push_str(diff_content.as_str());
+    review_msgs
+}
+
+// 从模型回复中取出 JSON 数组，兼容 ``` 代码块和前后多余的文字
+fn parse_findings(answer: &str) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let start = answer.find('[');
+    let end = answer.rfind(']');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &answer[start..=end],
+        _ => return Err(format!("Fail to find review findings in the response:\n{}", answer).into()),
+    };
+
+    let mut findings: Vec<Finding> = serde_json::from_str(json)?;
+    // 按文件分组，同一文件内按严重程度从高到低排列
+    findings.sort_by(|a, b| {
+        a.file
+            .cmp(&b.file)
+            .then(b.severity.cmp(&a.severity))
+            .then(a.line.cmp(&b.line))
+    });
+    Ok(findings)
+}
+
+fn print_findings(findings: &[Finding]) {
+    println!("============================================================================");
+    println!("Review Findings");
+    println!("============================================================================");
+
+    if findings.is_empty() {
+        println!("No findings.");
+        return;
+    }
+
+    let mut cur_file = "";
+    for finding in findings.iter() {
+        if finding.file != cur_file {
+            cur_file = finding.file.as_str();
+            println!("\n{}", cur_file);
+        }
+        let line = finding.line.map(|l| format!("line {}", l)).unwrap_or(String::from("-"));
+        println!(
+            "  [{}] {} ({}): {}",
+            finding.severity.as_str().to_uppercase(),
+            line,
+            finding.category,
+            finding.message
+        );
+        if !finding.suggestion.is_empty() {
+            println!("      suggestion: {}", finding.suggestion);
+        }
+    }
+
+    let mut counts: Vec<String> = vec![];
+    for severity in [Severity::Critical, Severity::High, Severity::Medium, Severity::Low, Severity::Info] {
+        let n = findings.iter().filter(|f| f.severity == severity).count();
+        if n > 0 {
+            counts.push(format!("{} {}", n, severity.as_str()));
+        }
+    }
+    println!("\n{} finding(s): {}", findings.len(), counts.join(", "));
+}
+
+// 规则 ID 由类别决定，例如 aigit/security
+fn rule_id(finding: &Finding) -> String {
+    let category = finding.category.trim().to_lowercase();
+    if category.is_empty() {
+        return String::from("aigit/general");
+    }
+    format!("aigit/{}", category.replace(' ', "-"))
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+// SARIF 2.1.0，可直接被 CI 面板和编辑器读取
+fn findings_sarif(findings: &[Finding]) -> serde_json::Value {
+    let mut rule_ids: Vec<String> = findings.iter().map(rule_id).collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    let rules: Vec<serde_json::Value> = rule_ids
+        .iter()
+        .map(|id| {
+            serde_json::json!({
+                "id": id,
+                "name": id.trim_start_matches("aigit/"),
+                "shortDescription": { "text": format!("AI review: {}", id.trim_start_matches("aigit/")) },
+            })
+        })
+        .collect();
+
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut message = finding.message.clone();
+            if !finding.suggestion.is_empty() {
+                message.push_str(format!("\nSuggestion: {}", finding.suggestion).as_str());
+            }
+            let mut location = serde_json::json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": finding.file },
+                }
+            });
+            if let Some(line) = finding.line.filter(|l| *l > 0) {
+                location["physicalLocation"]["region"] = serde_json::json!({ "startLine": line });
+            }
+            serde_json::json!({
+                "ruleId": rule_id(finding),
+                "level": sarif_level(finding.severity),
+                "message": { "text": message },
+                "locations": [location],
+                "properties": { "severity": finding.severity.as_str() },
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "aigit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn findings_markdown(findings: &[Finding]) -> String {
+    let mut md = String::from("## AI Review Findings\n");
+    if findings.is_empty() {
+        md.push_str("\nNo findings.\n");
+        return md;
+    }
+
+    let mut cur_file = "";
+    for finding in findings.iter() {
+        if finding.file != cur_file {
+            cur_file = finding.file.as_str();
+            md.push_str(format!("\n### `{}`\n\n", cur_file).as_str());
+            md.push_str("| Severity | Line | Rule | Message | Suggestion |\n");
+            md.push_str("| --- | --- | --- | --- | --- |\n");
+        }
+        let line = finding.line.map(|l| l.to_string()).unwrap_or(String::from("-"));
+        md.push_str(
+            format!(
+                "| {} | {} | `{}` | {} | {} |\n",
+                finding.severity.as_str(),
+                line,
+                rule_id(finding),
+                finding.message.replace('|', "\\|").replace('\n', " "),
+                finding.suggestion.replace('|', "\\|").replace('\n', " ")
+            )
+            .as_str(),
+        );
+    }
+    md
+}
+
+fn output_findings(findings: &[Finding], format: ReviewFormat) -> Result<(), Box<dyn Error>> {
+    match format {
+        ReviewFormat::Text => print_findings(findings),
+        ReviewFormat::Json => println!("{}", serde_json::to_string_pretty(findings)?),
+        ReviewFormat::Sarif => println!("{}", serde_json::to_string_pretty(&findings_sarif(findings))?),
+        ReviewFormat::Markdown => print!("{}", findings_markdown(findings)),
+    }
+    Ok(())
+}
+
+// 请求模型审查 diff 并解析结果
+pub fn review_findings(diff_content: String) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_review(prepare_for_ai(diff_content)?),
+    )?;
+    debug!("review answer: {}", answer);
+    parse_findings(answer.as_str())
+}
+
+pub fn handle_review(
+    range: Option<String>,
+    staged: bool,
+    fail_on: Option<Severity>,
+    format: ReviewFormat,
+) -> Result<(), Box<dyn Error>> {
+    let diff_content = git_diff(range.unwrap_or("HEAD".to_string()), staged)?;
+    let findings = if diff_content.trim().is_empty() {
+        eprintln!("Nothing to review.");
+        vec![]
+    } else {
+        // 进度信息输出到 stderr，保证 stdout 只包含结果
+        eprintln!("Reviewing...");
+        review_findings(diff_content)?
+    };
+    output_findings(&findings, format)?;
+
+    // 存在不低于阈值的问题时返回错误，可用作 pre-push 检查
+    let threshold = match fail_on {
+        Some(severity) => Some(severity),
+        None => Severity::parse(G_REVIEW_FAIL_ON.as_str()),
+    };
+    if let Some(threshold) = threshold {
+        let failed = findings.iter().filter(|f| f.severity >= threshold).count();
+        if failed > 0 {
+            return Err(format!(
+                "{} finding(s) at or above severity '{}'",
+                failed,
+                threshold.as_str()
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::api::common::{ChatFn, get_chat};
+use crate::command::{
+    edit_in_editor, get_git_res, git_output, git_show, prepare_for_ai, prepare_lines_for_ai, prompt_create_commit_msg,
+};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, G_REWORD_PROTECTED};
+use crate::explain::GitError;
+use crate::history::sample_commit_examples;
+use crate::scope::infer_scopes;
+use crate::split::read_line;
+use crate::ticket::{apply_tickets, branch_tickets};
+
+// 编辑提交信息时使用的临时文件，位于 .git 目录下
+const REWORD_EDIT_PATH: &str = "AIGIT_REWORD_MSG";
+// 新旧提交信息并排展示时每列的宽度
+const COLUMN_WIDTH: usize = 38;
+
+// 需要重建的一个提交
+struct RebuildCommit {
+    hash: String,
+    parents: Vec<String>,
+}
+
+// 待改写的提交和新的提交信息
+struct Reword {
+    hash: String,
+    old_message: String,
+    new_message: String,
+}
+
+// 当前分支上需要重建的提交：从 oldest 到 HEAD，旧的在前
+fn git_rebuild_list(oldest: &str) -> Result<Vec<RebuildCommit>, Box<dyn Error>> {
+    let parents = git_output(&["rev-list", "--parents", "-n1", oldest])?;
+    let mut args: Vec<String> = vec![
+        String::from("rev-list"),
+        String::from("--reverse"),
+        String::from("--parents"),
+        String::from("HEAD"),
+    ];
+    let oldest_parents: Vec<&str> = parents.split_whitespace().skip(1).collect();
+    if !oldest_parents.is_empty() {
+        args.push(String::from("--not"));
+        args.extend(oldest_parents.iter().map(|p| p.to_string()));
+    }
+
+    let output = git_output(&args)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut hashes = line.split_whitespace().map(|h| h.to_string());
+            Some(RebuildCommit { hash: hashes.next()?, parents: hashes.collect() })
+        })
+        .collect())
+}
+
+// 已经在受保护分支或远程分支上的提交，改写后会和别人的历史分叉
+fn published_refs(oldest: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut refs: Vec<String> = vec![];
+    for branch in G_REWORD_PROTECTED.iter() {
+        let branch_ref = format!("refs/heads/{}", branch);
+        if git_output(&["rev-parse", "-q", "--verify", branch_ref.as_str()]).is_ok()
+            && git_output(&["merge-base", "--is-ancestor", oldest, branch_ref.as_str()]).is_ok()
+        {
+            refs.push(branch.clone());
+        }
+    }
+    let remotes = git_output(&["branch", "-r", "--format=%(refname:short)", "--contains", oldest])?;
+    refs.extend(remotes.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()));
+    Ok(refs)
+}
+
+fn git_commit_message(hash: &str) -> Result<String, Box<dyn Error>> {
+    Ok(git_output(&["log", "-1", "--format=%B", hash])?.trim_end().to_string())
+}
+
+fn generate_message(hash: &str, tickets: &[String]) -> Result<String, Box<dyn Error>> {
+    let paths: Vec<String> = git_output(&["show", "--no-color", "--name-only", "--format=", hash])?
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let examples = sample_commit_examples(&paths)?;
+    let scopes = infer_scopes(&paths);
+
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let mut message = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_create_commit_msg(
+            prepare_for_ai(git_show(hash.to_string())?)?,
+            examples,
+            prepare_lines_for_ai(&scopes)?,
+            prepare_lines_for_ai(tickets)?,
+        ),
+    )?;
+    if message.starts_with("```") {
+        message = message.trim_start_matches("```").trim_end_matches("```").to_string();
+    }
+    Ok(apply_tickets(message.trim().to_string(), tickets).trim().to_string())
+}
+
+// 按列宽折行，按字符计算宽度
+fn wrap_column(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+            continue;
+        }
+        for chunk in chars.chunks(COLUMN_WIDTH) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+fn print_side_by_side(reword: &Reword) {
+    let old = wrap_column(reword.old_message.as_str());
+    let new = wrap_column(reword.new_message.as_str());
+    println!("----------------------------------------------------------------------------");
+    println!("{}", &reword.hash[..7.min(reword.hash.len())]);
+    println!("{:<width$} | New", "Old", width = COLUMN_WIDTH);
+    println!("{:-<width$}-+-{:-<width$}", "", "", width = COLUMN_WIDTH);
+    for idx in 0..old.len().max(new.len()) {
+        println!(
+            "{:<width$} | {}",
+            old.get(idx).map(|s| s.as_str()).unwrap_or(""),
+            new.get(idx).map(|s| s.as_str()).unwrap_or(""),
+            width = COLUMN_WIDTH
+        );
+    }
+}
+
+// 逐个确认新的提交信息，返回 false 表示放弃
+fn review_rewords(rewords: &mut [Reword]) -> Result<bool, Box<dyn Error>> {
+    for reword in rewords.iter_mut() {
+        print_side_by_side(reword);
+        loop {
+            let input = read_line("Commands: y = use new, n = keep old, e = edit new, q = quit\n> ")?;
+            match input.as_str() {
+                "y" => {}
+                "n" => reword.new_message = reword.old_message.clone(),
+                "e" => {
+                    let edited = edit_in_editor(reword.new_message.as_str(), REWORD_EDIT_PATH)?;
+                    if !edited.trim().is_empty() {
+                        reword.new_message = edited.trim().to_string();
+                    }
+                }
+                "q" => return Ok(false),
+                _ => {
+                    println!("Unknown command.");
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+    Ok(true)
+}
+
+// 用原提交的 tree 和作者创建新提交，提交者为当前用户
+fn git_commit_tree(hash: &str, parents: &[String], message: &str) -> Result<String, Box<dyn Error>> {
+    let author = git_output(&["log", "-1", "--format=%an%x00%ae%x00%ad", "--date=raw", hash])?;
+    let fields: Vec<&str> = author.trim_end_matches('\n').split('\0').collect();
+    let [name, email, date] = fields.as_slice() else {
+        return Err(format!("Fail to read the author of {}", hash).into());
+    };
+
+    let mut args: Vec<String> = vec![String::from("commit-tree"), format!("{}^{{tree}}", hash)];
+    for parent in parents.iter() {
+        args.push(String::from("-p"));
+        args.push(parent.clone());
+    }
+    let mut child = Command::new("git")
+        .args(&args)
+        .env("GIT_AUTHOR_NAME", name)
+        .env("GIT_AUTHOR_EMAIL", email)
+        .env("GIT_AUTHOR_DATE", date)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes())?;
+        stdin.write_all(b"\n")?;
+    }
+    Ok(get_git_res(child).map_err(|e| GitError::wrap(&args, e))?.trim().to_string())
+}
+
+// 不经过工作区重建提交，tree 不变，所以工作区和暂存区都不受影响
+fn rewrite_history(rebuild: &[RebuildCommit], rewords: &[Reword]) -> Result<String, Box<dyn Error>> {
+    let mut mapping: Vec<(String, String)> = vec![];
+    for commit in rebuild.iter() {
+        let parents: Vec<String> = commit
+            .parents
+            .iter()
+            .map(|p| mapping.iter().find(|(old, _)| old == p).map(|(_, new)| new.clone()).unwrap_or(p.clone()))
+            .collect();
+        let message = match rewords.iter().find(|r| r.hash == commit.hash) {
+            Some(reword) => reword.new_message.clone(),
+            None => git_commit_message(commit.hash.as_str())?,
+        };
+        let new_hash = git_commit_tree(commit.hash.as_str(), &parents, message.as_str())?;
+        mapping.push((commit.hash.clone(), new_hash));
+    }
+    mapping.last().map(|(_, new)| new.clone()).ok_or("Nothing to rewrite".into())
+}
+
+pub fn handle_reword(range: String, force: bool, yes: bool) -> Result<(), Box<dyn Error>> {
+    let range = if range.contains("..") { range } else { format!("{}..HEAD", range) };
+    let hashes: Vec<String> = git_output(&["rev-list", "--reverse", range.as_str()])?
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let Some(oldest) = hashes.first() else {
+        return Err(format!("No commits in {}", range).into());
+    };
+
+    let rebuild = git_rebuild_list(oldest.as_str())?;
+    if let Some(hash) = hashes.iter().find(|h| !rebuild.iter().any(|c| c.hash == **h)) {
+        return Err(format!("{} is not on the current branch", &hash[..7.min(hash.len())]).into());
+    }
+    if rebuild.iter().any(|c| c.parents.len() > 1) {
+        return Err("The history to rewrite contains merge commits, reword only supports linear history".into());
+    }
+
+    let published = published_refs(oldest.as_str())?;
+    if !published.is_empty() {
+        if !force {
+            return Err(format!(
+                "Commits in {} are already on {}, rewriting them diverges from the published history; use --force to reword anyway",
+                range,
+                published.join(", ")
+            )
+            .into());
+        }
+        eprintln!("Warning: rewriting commits already on {}", published.join(", "));
+    }
+    if !yes && !std::io::stdin().is_terminal() {
+        return Err("Not running in a terminal, use --yes to accept the new messages".into());
+    }
+
+    let tickets = branch_tickets()?;
+    let mut rewords: Vec<Reword> = vec![];
+    for (idx, hash) in hashes.iter().enumerate() {
+        eprintln!("Generating message {}/{} for {}...", idx + 1, hashes.len(), &hash[..7.min(hash.len())]);
+        rewords.push(Reword {
+            hash: hash.clone(),
+            old_message: git_commit_message(hash.as_str())?,
+            new_message: generate_message(hash.as_str(), &tickets)?,
+        });
+    }
+
+    if yes {
+        for reword in rewords.iter() {
+            print_side_by_side(reword);
+        }
+    } else if !review_rewords(&mut rewords)? {
+        println!("Reword cancelled, the history is untouched.");
+        return Ok(());
+    }
+    rewords.retain(|r| r.new_message != r.old_message);
+    if rewords.is_empty() {
+        println!("No message changed, the history is untouched.");
+        return Ok(());
+    }
+
+    let old_head = git_output(&["rev-parse", "HEAD"])?.trim().to_string();
+    let new_head = rewrite_history(&rebuild, &rewords)?;
+    git_output(&["update-ref", "-m", "aigit reword", "HEAD", new_head.as_str(), old_head.as_str()])?;
+    println!(
+        "Reworded {} commit(s). The previous history is {}, restore it with `git reset --keep {}`.",
+        rewords.len(),
+        &old_head[..7.min(old_head.len())],
+        &old_head[..7.min(old_head.len())]
+    );
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use globset::{GlobBuilder, GlobMatcher};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::path::Path;
+
+use crate::config::{G_AUTO_SCOPE, G_CONFIG, repo_root};
+
+// CODEOWNERS 可能存放的位置
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+// 一条 glob -> scope 映射规则
+struct ScopeRule {
+    matcher: GlobMatcher,
+    scope: String,
+}
+
+fn new_rule(pattern: &str, scope: &str) -> Option<ScopeRule> {
+    match GlobBuilder::new(pattern).literal_separator(true).build() {
+        Ok(glob) => Some(ScopeRule {
+            matcher: glob.compile_matcher(),
+            scope: scope.to_string(),
+        }),
+        Err(e) => {
+            error!("Invalid scope pattern '{}': {}", pattern, e);
+            None
+        }
+    }
+}
+
+// 目录规则：<dir>/** -> 目录名
+fn dir_rule(dir: &str) -> Option<ScopeRule> {
+    let name = dir.rsplit('/').next().unwrap_or(dir);
+    new_rule(format!("{}/**", dir).as_str(), name)
+}
+
+// glob 中第一个通配符之前的部分
+fn literal_prefix(pattern: &str) -> &str {
+    let end = pattern.find(['*', '?', '[', '{']).unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+// 读取配置中的 [scopes] 表，例如 "aigit/src/api/**" = "api"。
+// 表是无序的，按字面前缀从长到短排序，重叠时更具体的规则优先
+fn configured_rules() -> Vec<ScopeRule> {
+    let table = match G_CONFIG.get_table("scopes") {
+        Ok(table) => table,
+        Err(_) => return vec![],
+    };
+    let mut entries: Vec<(String, config::Value)> = table.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| {
+        literal_prefix(b)
+            .len()
+            .cmp(&literal_prefix(a).len())
+            .then(b.len().cmp(&a.len()))
+            .then(a.cmp(b))
+    });
+
+    let mut rules: Vec<ScopeRule> = vec![];
+    for (pattern, value) in entries {
+        match value.into_string() {
+            Ok(scope) => rules.extend(new_rule(pattern.as_str(), scope.as_str())),
+            Err(e) => error!("Invalid scope for '{}': {}", pattern, e),
+        }
+    }
+    rules
+}
+
+// 将 workspace 成员（可能包含通配符，如 crates/*）展开成实际存在的目录
+fn expand_member_dirs(root: &Path, pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let mut dirs: Vec<String> = vec![String::new()];
+
+    for component in pattern.split('/') {
+        let mut next: Vec<String> = vec![];
+        for dir in dirs.iter() {
+            let join = |name: &str| {
+                if dir.is_empty() { name.to_string() } else { format!("{}/{}", dir, name) }
+            };
+
+            if !component.contains(['*', '?', '[']) {
+                next.push(join(component));
+                continue;
+            }
+
+            let matcher = match GlobBuilder::new(component.replace("**", "*").as_str()).build() {
+                Ok(glob) => glob.compile_matcher(),
+                Err(_) => continue,
+            };
+            let entries = match root.join(dir).read_dir() {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if entry.path().is_dir() && matcher.is_match(name.as_str()) {
+                    next.push(join(name.as_str()));
+                }
+            }
+        }
+        dirs = next;
+    }
+
+    dirs.into_iter()
+        .filter(|dir| !dir.is_empty() && root.join(dir).is_dir())
+        .collect()
+}
+
+// Cargo.toml 中的 [workspace] members
+fn cargo_workspace_rules(root: &Path) -> Vec<ScopeRule> {
+    let manifest = root.join("Cargo.toml");
+    if !manifest.exists() {
+        return vec![];
+    }
+
+    let members = config::Config::builder()
+        .add_source(config::File::from(manifest).format(config::FileFormat::Toml))
+        .build()
+        .and_then(|cargo| cargo.get_array("workspace.members"));
+    let members = match members {
+        Ok(members) => members,
+        Err(_) => return vec![],
+    };
+
+    let mut rules: Vec<ScopeRule> = vec![];
+    for member in members {
+        if let Ok(member) = member.into_string() {
+            for dir in expand_member_dirs(root, member.as_str()) {
+                rules.extend(dir_rule(dir.as_str()));
+            }
+        }
+    }
+    rules
+}
+
+// package.json 中的 workspaces，支持数组和 { "packages": [...] } 两种写法
+fn npm_workspace_rules(root: &Path) -> Vec<ScopeRule> {
+    let content = match std::fs::read_to_string(root.join("package.json")) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+    let package: serde_json::Value = match serde_json::from_str(content.as_str()) {
+        Ok(package) => package,
+        Err(e) => {
+            debug!("Fail to parse package.json: {}", e);
+            return vec![];
+        }
+    };
+
+    let workspaces = match &package["workspaces"] {
+        serde_json::Value::Array(list) => list.clone(),
+        serde_json::Value::Object(obj) => obj
+            .get("packages")
+            .and_then(|p| p.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    let mut rules: Vec<ScopeRule> = vec![];
+    for workspace in workspaces.iter().filter_map(|w| w.as_str()) {
+        for dir in expand_member_dirs(root, workspace) {
+            rules.extend(dir_rule(dir.as_str()));
+        }
+    }
+    rules
+}
+
+// 把 CODEOWNERS 中的 gitignore 风格路径转换为 glob
+fn codeowners_globs(pattern: &str) -> Vec<String> {
+    let anchored = pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/');
+    let path = pattern.trim_start_matches('/');
+    let base = if anchored { path.to_string() } else { format!("**/{}", path) };
+
+    if base.ends_with('/') {
+        vec![format!("{}**", base)]
+    } else {
+        vec![base.clone(), format!("{}/**", base)]
+    }
+}
+
+// 从 owner 中取出 scope 名称：@org/team-api -> team-api，@alice -> alice，a@b.c -> a
+fn owner_scope(owner: &str) -> String {
+    let owner = owner.trim_start_matches('@');
+    let owner = owner.rsplit('/').next().unwrap_or(owner);
+    owner.split('@').next().unwrap_or(owner).to_string()
+}
+
+// CODEOWNERS 中后出现的规则优先级更高，因此倒序返回
+fn codeowners_rules(root: &Path) -> Vec<ScopeRule> {
+    let content = match CODEOWNERS_PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(root.join(path)).ok())
+    {
+        Some(content) => content,
+        None => return vec![],
+    };
+
+    let mut rules: Vec<ScopeRule> = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(pattern), Some(owner)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        for glob in codeowners_globs(pattern) {
+            rules.extend(new_rule(glob.as_str(), owner_scope(owner).as_str()));
+        }
+    }
+    rules.reverse();
+    rules
+}
+
+// 按顺序收集规则：配置的 [scopes] 优先，其次是 workspace，最后是 CODEOWNERS
+fn collect_rules() -> Vec<ScopeRule> {
+    let mut rules = configured_rules();
+
+    if *G_AUTO_SCOPE && let Some(root) = repo_root() {
+        let root = Path::new(root.as_str());
+        rules.extend(cargo_workspace_rules(root));
+        rules.extend(npm_workspace_rules(root));
+        rules.extend(codeowners_rules(root));
+    }
+    rules
+}
+
+// 根据修改的文件路径推断 scope，按涉及文件数从多到少排序
+pub fn infer_scopes(paths: &[String]) -> Vec<String> {
+    let rules = collect_rules();
+    if rules.is_empty() {
+        return vec![];
+    }
+
+    let mut counts: Vec<(String, usize)> = vec![];
+    for path in paths {
+        let Some(rule) = rules.iter().find(|rule| rule.matcher.is_match(path.as_str())) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(scope, _)| *scope == rule.scope) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((rule.scope.clone(), 1)),
+        }
+    }
+
+    // 稳定排序，数量相同时保持首次出现的顺序
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    debug!("inferred scopes: {:?}", counts);
+    counts.into_iter().map(|(scope, _)| scope).collect()
+}
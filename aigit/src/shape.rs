@@ -0,0 +1,152 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use crate::command::get_git_res;
+use crate::explain::GitError;
+use crate::config::{
+    G_DIFF_ALGORITHM, G_DIFF_CONTEXT, G_DIFF_FIND_COPIES, G_DIFF_FIND_RENAMES,
+    G_DIFF_FUNCTION_CONTEXT, G_DIFF_IGNORE_WHITESPACE, G_DIFF_TOKEN_BUDGET,
+};
+
+// auto 模式下依次尝试的上下文行数，从多到少
+const AUTO_CONTEXT_STEPS: [u32; 5] = [10, 6, 3, 1, 0];
+// 粗略估算：每个 token 约 4 个字符
+const CHARS_PER_TOKEN: usize = 4;
+
+const DIFF_ALGORITHMS: [&str; 5] = ["default", "myers", "minimal", "patience", "histogram"];
+
+// 命令行上的 diff 参数，优先于配置文件
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "Diff options")]
+pub struct DiffArgs {
+    /// Lines of context around changes, or `auto` to fit the token budget
+    #[arg(long, global = true, value_name = "N|auto")]
+    pub context: Option<String>,
+    /// Show the whole function as context
+    #[arg(long, global = true)]
+    pub function_context: bool,
+    /// Ignore whitespace-only changes
+    #[arg(long, global = true)]
+    pub ignore_whitespace: bool,
+    /// Rename detection similarity threshold in percent (0 disables)
+    #[arg(long, global = true, value_name = "PERCENT")]
+    pub find_renames: Option<u32>,
+    /// Copy detection similarity threshold in percent (0 disables)
+    #[arg(long, global = true, value_name = "PERCENT")]
+    pub find_copies: Option<u32>,
+    /// Diff algorithm: default, myers, minimal, patience or histogram
+    #[arg(long, global = true, value_name = "ALGORITHM")]
+    pub diff_algorithm: Option<String>,
+}
+
+static G_DIFF_ARGS: OnceLock<DiffArgs> = OnceLock::new();
+
+pub fn set_diff_args(args: DiffArgs) {
+    let _ = G_DIFF_ARGS.set(args);
+}
+
+// 合并命令行和配置文件后的 diff 参数
+struct DiffShape {
+    // None 表示 auto
+    context: Option<u32>,
+    function_context: bool,
+    ignore_whitespace: bool,
+    find_renames: u32,
+    find_copies: u32,
+    algorithm: String,
+}
+
+fn diff_shape() -> Result<DiffShape, Box<dyn Error>> {
+    let cli = G_DIFF_ARGS.get().cloned().unwrap_or_default();
+
+    let context = cli.context.unwrap_or(G_DIFF_CONTEXT.clone());
+    let context = if context == "auto" {
+        None
+    } else {
+        Some(context.parse::<u32>().map_err(|_| {
+            format!("Invalid diff context '{}', expected a number or 'auto'", context)
+        })?)
+    };
+
+    let algorithm = cli.diff_algorithm.unwrap_or(G_DIFF_ALGORITHM.clone());
+    if !DIFF_ALGORITHMS.contains(&algorithm.as_str()) {
+        return Err(format!(
+            "Invalid diff algorithm '{}', expected one of: {}",
+            algorithm,
+            DIFF_ALGORITHMS.join(", ")
+        )
+        .into());
+    }
+
+    Ok(DiffShape {
+        context,
+        function_context: cli.function_context || *G_DIFF_FUNCTION_CONTEXT,
+        ignore_whitespace: cli.ignore_whitespace || *G_DIFF_IGNORE_WHITESPACE,
+        find_renames: cli.find_renames.unwrap_or(*G_DIFF_FIND_RENAMES),
+        find_copies: cli.find_copies.unwrap_or(*G_DIFF_FIND_COPIES),
+        algorithm,
+    })
+}
+
+fn shape_args(shape: &DiffShape, context: u32) -> Vec<String> {
+    let mut args: Vec<String> = vec![format!("-U{}", context)];
+
+    if shape.function_context {
+        args.push("--function-context".to_string());
+    }
+    if shape.ignore_whitespace {
+        args.push("--ignore-all-space".to_string());
+        args.push("--ignore-blank-lines".to_string());
+    }
+    if shape.find_renames > 0 {
+        args.push(format!("--find-renames={}%", shape.find_renames.min(100)));
+    } else {
+        args.push("--no-renames".to_string());
+    }
+    if shape.find_copies > 0 {
+        args.push(format!("--find-copies={}%", shape.find_copies.min(100)));
+    }
+    if shape.algorithm != "default" {
+        args.push(format!("--diff-algorithm={}", shape.algorithm));
+    }
+    args
+}
+
+fn run_git(subcommand: &str, shape_args: Vec<String>, rest: &[String]) -> Result<String, Box<dyn Error>> {
+    let mut args: Vec<String> = vec![subcommand.to_string()];
+    args.extend(shape_args);
+    args.extend(rest.iter().cloned());
+    debug!("git {:?}", args);
+
+    let child = Command::new("git")
+        .args(&args)
+        .stdout(Stdio::piped()) // 捕获标准输出
+        .stderr(Stdio::piped()) // 捕获错误输出
+        .spawn()?; // 异步启动
+
+    get_git_res(child).map_err(|e| GitError::wrap(&args, e))
+}
+
+// 以配置的 diff 参数运行 `git <subcommand> <rest>`（diff、show 等）。
+// auto 模式下从最丰富的上下文开始，逐步减少直到输出满足 token 预算。
+pub fn git_shaped(subcommand: &str, rest: &[String]) -> Result<String, Box<dyn Error>> {
+    let shape = diff_shape()?;
+
+    if let Some(context) = shape.context {
+        return run_git(subcommand, shape_args(&shape, context), rest);
+    }
+
+    let budget = *G_DIFF_TOKEN_BUDGET * CHARS_PER_TOKEN;
+    let mut output = String::new();
+    for context in AUTO_CONTEXT_STEPS.iter() {
+        output = run_git(subcommand, shape_args(&shape, *context), rest)?;
+        if output.len() <= budget {
+            debug!("auto diff context: {} lines", context);
+            break;
+        }
+    }
+    Ok(output)
+}
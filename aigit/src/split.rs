@@ -0,0 +1,378 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{get_git_res, git_output, prepare_for_ai};
+use crate::explain::GitError;
+use crate::filter::ignored_paths;
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::ticket::{apply_tickets, branch_tickets};
+
+// 拆分前暂存区的完整 patch，失败时可用 `git apply --cached` 恢复
+const SPLIT_BACKUP_PATH: &str = "aigit-split.patch";
+
+// 需要整体处理的文件：新增、删除、重命名、复制和二进制文件
+const WHOLE_FILE_MARKERS: [&str; 6] = [
+    "new file mode",
+    "deleted file mode",
+    "rename from",
+    "copy from",
+    "Binary files",
+    "GIT binary patch",
+];
+
+// 一个文件的 diff：文件头和若干 hunk
+struct FileDiff {
+    path: String,
+    header: Vec<String>,
+    hunks: Vec<String>,
+    // 不能按 hunk 拆分，只能整体提交
+    whole: bool,
+}
+
+// 可分配的最小单元：某个文件的一个 hunk，或整个文件
+struct Unit {
+    file: usize,
+    // whole 文件时为 None
+    hunk: Option<usize>,
+}
+
+// 拆分计划中的一次提交
+#[derive(Debug, Clone, Deserialize)]
+struct PlannedCommit {
+    message: String,
+    hunks: Vec<usize>,
+}
+
+fn prompt_split(hunks: String, count: usize) -> Vec<ChatMessage> {
+    let mut split_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: format!(
+"You are an expert in atomic commits and conventional commits. The user will provide the hunks of a large staged change, each labelled `H<n>` with its file path.
+
+Cluster the hunks into logical commits:
+    - Each commit must contain one coherent change that builds on the previous commits
+    - Every hunk from H1 to H{} must be assigned to exactly one commit
+    - Order the commits so that each one makes sense on its own (e.g. refactoring before the feature that uses it)
+    - Give every commit a conventional commit message: `<type>(<scope>): <subject>`, optionally followed by an empty line and a short body
+
+Do not speculate about context not present in the hunks.
+Output ONLY a JSON array with no additional text and no markdown, for example:
+[{{\"message\": \"refactor(api): extract request builder\", \"hunks\": [1, 3]}}, {{\"message\": \"feat(api): add retry on timeout\", \"hunks\": [2, 4]}}]",
+                count
+            ),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: "Please split the following staged change. \n".to_string(),
+        },
+    ];
+
+    split_msgs[1].content.push_str(hunks.as_str());
+    split_msgs
+}
+
+// 可直接应用的暂存区 diff，固定格式不受 [diff] 配置影响
+fn git_staged_patch() -> Result<String, Box<dyn Error>> {
+    git_output(&["diff", "--staged", "--no-color", "--no-ext-diff", "--binary", "-U3"])
+}
+
+fn parse_patch(patch: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = vec![];
+    for line in patch.lines() {
+        if line.starts_with("diff --git ") {
+            let path = match line.rsplit_once(" b/") {
+                Some((_, path)) => path.to_string(),
+                None => line.to_string(),
+            };
+            files.push(FileDiff {
+                path,
+                header: vec![line.to_string()],
+                hunks: vec![],
+                whole: false,
+            });
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") && !file.whole {
+            file.hunks.push(String::new());
+        }
+        match file.hunks.last_mut() {
+            Some(hunk) => {
+                hunk.push_str(line);
+                hunk.push('\n');
+            }
+            None => {
+                if WHOLE_FILE_MARKERS.iter().any(|marker| line.starts_with(marker)) {
+                    file.whole = true;
+                }
+                file.header.push(line.to_string());
+            }
+        }
+    }
+
+    for file in files.iter_mut() {
+        if file.hunks.is_empty() {
+            file.whole = true;
+        }
+    }
+    files
+}
+
+fn collect_units(files: &[FileDiff]) -> Vec<Unit> {
+    let mut units: Vec<Unit> = vec![];
+    for (idx, file) in files.iter().enumerate() {
+        if file.whole {
+            units.push(Unit { file: idx, hunk: None });
+        } else {
+            for hunk in 0..file.hunks.len() {
+                units.push(Unit { file: idx, hunk: Some(hunk) });
+            }
+        }
+    }
+    units
+}
+
+// 给模型看的 hunk 列表，hunk 没有文件头，被忽略的文件在这里去掉内容
+fn describe_units(files: &[FileDiff], units: &[Unit]) -> Result<String, Box<dyn Error>> {
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let ignored = ignored_paths(&paths)?;
+
+    let mut content = String::new();
+    for (idx, unit) in units.iter().enumerate() {
+        let file = &files[unit.file];
+        content.push_str(format!("\n### H{} {}\n", idx + 1, file.path).as_str());
+        if ignored.contains(&file.path) {
+            content.push_str("(content omitted, the file is excluded by .aigitignore)\n");
+            continue;
+        }
+        match unit.hunk {
+            Some(hunk) => content.push_str(file.hunks[hunk].as_str()),
+            None => {
+                content.push_str(file.header[1..].join("\n").as_str());
+                content.push('\n');
+                content.push_str(file.hunks.concat().as_str());
+            }
+        }
+    }
+    Ok(content)
+}
+
+fn parse_plan(answer: &str, count: usize) -> Result<Vec<PlannedCommit>, Box<dyn Error>> {
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Err(format!("Fail to find the split plan in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the split plan in the response:\n{}", answer).into());
+    }
+    let mut plan: Vec<PlannedCommit> = serde_json::from_str(&answer[start..=end])?;
+
+    // 去掉重复和越界的 hunk，遗漏的 hunk 放到最后一个提交
+    let mut assigned: Vec<bool> = vec![false; count];
+    for commit in plan.iter_mut() {
+        commit.hunks.retain(|h| {
+            if *h < 1 || *h > count || assigned[*h - 1] {
+                return false;
+            }
+            assigned[*h - 1] = true;
+            true
+        });
+    }
+    plan.retain(|commit| !commit.hunks.is_empty());
+
+    let missing: Vec<usize> = (1..=count).filter(|h| !assigned[h - 1]).collect();
+    if !missing.is_empty() {
+        match plan.last_mut() {
+            Some(commit) => commit.hunks.extend(missing),
+            None => plan.push(PlannedCommit {
+                message: String::from("chore: apply staged changes"),
+                hunks: missing,
+            }),
+        }
+    }
+    Ok(plan)
+}
+
+fn print_plan(files: &[FileDiff], units: &[Unit], plan: &[PlannedCommit]) {
+    println!("============================================================================");
+    println!("Split Plan");
+    println!("============================================================================");
+    for (idx, commit) in plan.iter().enumerate() {
+        println!("[{}] {}", idx + 1, commit.message.lines().next().unwrap_or(""));
+        for line in commit.message.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+            println!("      {}", line);
+        }
+        for h in commit.hunks.iter() {
+            let unit = &units[h - 1];
+            let file = &files[unit.file];
+            let detail = match unit.hunk {
+                Some(hunk) => file.hunks[hunk].lines().next().unwrap_or("").to_string(),
+                None => String::from("(whole file)"),
+            };
+            println!("    H{:<3} {}  {}", h, file.path, detail);
+        }
+    }
+}
+
+pub(crate) fn read_line(prompt: &str) -> Result<String, Box<dyn Error>> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+// 交互式调整计划，返回 false 表示放弃
+fn review_plan(files: &[FileDiff], units: &[Unit], plan: &mut Vec<PlannedCommit>) -> Result<bool, Box<dyn Error>> {
+    loop {
+        print_plan(files, units, plan);
+        println!("\nCommands: a = apply, q = quit, m <H> <commit> = move hunk (commit {} creates a new one),", plan.len() + 1);
+        println!("          e <commit> = edit message, j <commit> <commit> = join the second into the first");
+        let input = read_line("> ")?;
+        let args: Vec<&str> = input.split_whitespace().collect();
+        let nums: Vec<usize> = args.iter().skip(1).filter_map(|a| a.trim_start_matches(['H', 'h']).parse().ok()).collect();
+
+        match (args.first().copied(), nums.as_slice()) {
+            (Some("a"), _) => return Ok(true),
+            (Some("q"), _) => return Ok(false),
+            (Some("m"), [h, to]) if *to >= 1 && *to <= plan.len() + 1 && *h >= 1 && *h <= units.len() => {
+                for commit in plan.iter_mut() {
+                    commit.hunks.retain(|x| x != h);
+                }
+                if *to == plan.len() + 1 {
+                    plan.push(PlannedCommit { message: String::from("chore: split changes"), hunks: vec![] });
+                }
+                plan[to - 1].hunks.push(*h);
+                plan[to - 1].hunks.sort();
+                plan.retain(|commit| !commit.hunks.is_empty());
+            }
+            (Some("e"), [c]) if *c >= 1 && *c <= plan.len() => {
+                let message = read_line("New message: ")?;
+                if !message.is_empty() {
+                    plan[c - 1].message = message;
+                }
+            }
+            (Some("j"), [a, b]) if a != b && *a >= 1 && *a <= plan.len() && *b >= 1 && *b <= plan.len() => {
+                let hunks = plan[b - 1].hunks.clone();
+                plan[a - 1].hunks.extend(hunks);
+                plan[a - 1].hunks.sort();
+                plan.remove(b - 1);
+            }
+            _ => println!("Unknown command."),
+        }
+    }
+}
+
+// 由选中的单元组成一个可以 `git apply` 的 patch
+fn build_patch(files: &[FileDiff], units: &[Unit], selected: &[usize]) -> String {
+    let mut patch = String::new();
+    for (idx, file) in files.iter().enumerate() {
+        let chosen: Vec<&Unit> = selected
+            .iter()
+            .map(|h| &units[h - 1])
+            .filter(|unit| unit.file == idx)
+            .collect();
+        if chosen.is_empty() {
+            continue;
+        }
+
+        patch.push_str(file.header.join("\n").as_str());
+        patch.push('\n');
+        for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
+            if file.whole || chosen.iter().any(|unit| unit.hunk == Some(hunk_idx)) {
+                patch.push_str(hunk.as_str());
+            }
+        }
+    }
+    patch
+}
+
+fn git_apply_cached(patch: &str) -> Result<(), Box<dyn Error>> {
+    let args = ["apply", "--cached", "--recount", "--whitespace=nowarn", "-"];
+    let mut child = Command::new("git")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped()) // 捕获标准输出
+        .stderr(Stdio::piped()) // 捕获错误输出
+        .spawn()?; // 异步启动
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes())?;
+    }
+    get_git_res(child).map_err(|e| GitError::wrap(&args, e))?;
+    Ok(())
+}
+
+fn apply_plan(files: &[FileDiff], units: &[Unit], plan: &[PlannedCommit], full_patch: &str) -> Result<(), Box<dyn Error>> {
+    let git_dir = git_output(&["rev-parse", "--git-dir"])?;
+    let backup = format!("{}/{}", git_dir.trim(), SPLIT_BACKUP_PATH);
+    std::fs::write(backup.as_str(), full_patch)?;
+
+    // 清空暂存区（不影响工作区），再按组依次暂存并提交
+    git_output(&["reset", "-q"])?;
+    for (idx, commit) in plan.iter().enumerate() {
+        let result = git_apply_cached(build_patch(files, units, &commit.hunks).as_str())
+            .and_then(|_| git_output(&["commit", "-q", "-m", commit.message.as_str()]).map(|_| ()));
+        if let Err(e) = result {
+            return Err(format!(
+                "Fail to apply commit {} of the split plan: {}\nThe original staged change is saved in {}, restore it with `git reset -q && git apply --cached {}` after resetting the commits made so far.",
+                idx + 1, e, backup, backup
+            )
+            .into());
+        }
+        println!("Committed [{}/{}] {}", idx + 1, plan.len(), commit.message.lines().next().unwrap_or(""));
+    }
+
+    std::fs::remove_file(backup)?;
+    Ok(())
+}
+
+pub fn handle_split(yes: bool) -> Result<(), Box<dyn Error>> {
+    let patch = git_staged_patch()?;
+    if patch.trim().is_empty() {
+        return Err("Nothing staged to split".into());
+    }
+
+    let files = parse_patch(patch.as_str());
+    let units = collect_units(&files);
+    if units.len() < 2 {
+        return Err("The staged change has only one hunk, use `aigit commit` instead".into());
+    }
+
+    println!("Clustering {} hunk(s) into commits...", units.len());
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_split(prepare_for_ai(describe_units(&files, &units)?)?, units.len()),
+    )?;
+    let mut plan = parse_plan(answer.as_str(), units.len())?;
+
+    let tickets = branch_tickets()?;
+    for commit in plan.iter_mut() {
+        commit.message = apply_tickets(commit.message.trim().to_string(), &tickets);
+    }
+
+    if yes {
+        print_plan(&files, &units, &plan);
+    } else {
+        if !std::io::stdin().is_terminal() {
+            print_plan(&files, &units, &plan);
+            return Err("Not running in a terminal, use --yes to apply the plan".into());
+        }
+        if !review_plan(&files, &units, &mut plan)? {
+            println!("Split cancelled, the staged change is untouched.");
+            return Ok(());
+        }
+    }
+
+    apply_plan(&files, &units, &plan, patch.as_str())
+}
@@ -0,0 +1,92 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+
+use crate::api::common::{ChatFn, get_chat};
+use crate::command::{git_commit, git_diff, git_output, prepare_for_ai, prepare_lines_for_ai, prompt_create_commit_msg};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::conventional::{RangeCommit, git_range_commits};
+use crate::history::sample_commit_examples;
+use crate::scope::infer_scopes;
+use crate::ticket::{apply_tickets, branch_tickets};
+
+// 被合并的提交信息和最终的 diff 一起交给模型，描述整体效果
+fn squash_content(commits: &[RangeCommit], diff_content: String) -> String {
+    let mut content = String::from("The following commits are squashed into one commit, oldest first:\n");
+    for commit in commits.iter() {
+        content.push_str(format!("- {}\n", commit.subject).as_str());
+        for line in commit.body.lines().filter(|l| !l.trim().is_empty()) {
+            content.push_str(format!("  {}\n", line).as_str());
+        }
+    }
+    content.push_str("\nDescribe the combined effect of the net patch below, not the individual steps.\n\n");
+    content.push_str(diff_content.as_str());
+    content
+}
+
+pub fn handle_squash(base: String, signoff: bool, directly: bool) -> Result<(), Box<dyn Error>> {
+    // 暂存区的修改会被一起提交，要求先处理掉
+    let staged = git_output(&["diff", "--cached", "--name-only"])?;
+    if !staged.trim().is_empty() {
+        return Err("There are staged changes, commit or unstage them before squashing".into());
+    }
+
+    let merge_base = git_output(&["merge-base", "HEAD", base.as_str()])?.trim().to_string();
+    let range = format!("{}..HEAD", merge_base);
+    let commits = git_range_commits(range.as_str())?;
+    if commits.len() < 2 {
+        return Err(format!("Less than two commits since {}, nothing to squash", base).into());
+    }
+
+    let paths: Vec<String> = git_output(&["diff", "--name-only", range.as_str()])?
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let diff_content = git_diff(range.clone(), false)?;
+
+    println!("============================================================================");
+    println!("Squashing {} commit(s) since {}", commits.len(), base);
+    println!("============================================================================");
+    for commit in commits.iter() {
+        println!("{} {}", &commit.hash[..7.min(commit.hash.len())], commit.subject);
+    }
+
+    println!("============================================================================");
+    println!("Generating commit message...\n");
+    let examples = sample_commit_examples(&paths)?;
+    let scopes = infer_scopes(&paths);
+    let tickets = branch_tickets()?;
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let mut cm_msg = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_create_commit_msg(
+            prepare_for_ai(squash_content(&commits, diff_content))?,
+            examples,
+            prepare_lines_for_ai(&scopes)?,
+            prepare_lines_for_ai(&tickets)?,
+        ),
+    )?;
+    if cm_msg.starts_with("```") {
+        cm_msg = cm_msg.trim_start_matches("```").trim_end_matches("```").to_string();
+    }
+    cm_msg = apply_tickets(cm_msg, &tickets);
+    println!("{}", cm_msg);
+
+    let old_head = git_output(&["rev-parse", "HEAD"])?.trim().to_string();
+    git_output(&["reset", "--soft", merge_base.as_str()])?;
+    if let Err(e) = git_commit(signoff, directly, false, cm_msg) {
+        // 提交失败或在编辑器中放弃时恢复原来的提交
+        git_output(&["reset", "--soft", old_head.as_str()])?;
+        eprintln!("The branch is restored to {}", &old_head[..7.min(old_head.len())]);
+        return Err(e);
+    }
+    println!(
+        "Squashed {} commit(s). The previous history is {}, restore it with `git reset --keep {}`.",
+        commits.len(),
+        &old_head[..7.min(old_head.len())],
+        &old_head[..7.min(old_head.len())]
+    );
+    Ok(())
+}
@@ -0,0 +1,140 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+use std::path::Path;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+
+// 每个提交的 diffstat 最多列出的文件数
+const STAT_FILES: u32 = 10;
+
+fn prompt_summary(window: String, commits: String) -> Vec<ChatMessage> {
+    let mut summary_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer writing a standup report. The user will provide the commits made in a time window, with their repository, author, date, message and diffstat.
+
+### Output Specification:
+    - Markdown that can be pasted into a chat message
+    - Group the work by theme (feature, bug fix area, refactoring, ...), not by commit; one bold theme line followed by bullet points
+    - Each bullet describes the outcome in plain words, followed by the short hashes in parentheses
+    - Mention the repository in a theme when several repositories are given
+    - Mention the author in a bullet when several authors are given
+    - Keep it short: at most 5 themes and 3 bullets per theme
+    - Do not speculate about context not present in the commits, and do not invent next steps"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    summary_msgs[1].content.push_str(format!("Please summarize the work {}.\n\n### Commits\n", window).as_str());
+    summary_msgs[1].content.push_str(commits.as_str());
+    summary_msgs
+}
+
+// `me` 解析为该仓库配置的 user.email
+fn resolve_authors(repo: &str, authors: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut resolved: Vec<String> = vec![];
+    for author in authors.iter() {
+        if author == "me" {
+            let email = git_output(&["-C", repo, "config", "user.email"])
+                .map_err(|_| format!("`me` needs user.email to be set in {}", repo))?;
+            resolved.push(email.trim().to_string());
+        } else {
+            resolved.push(author.clone());
+        }
+    }
+    Ok(resolved)
+}
+
+// 仓库名取根目录的目录名
+fn repo_name(repo: &str) -> Result<String, Box<dyn Error>> {
+    let root = git_output(&["-C", repo, "rev-parse", "--show-toplevel"])?;
+    Ok(Path::new(root.trim())
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(root.trim().to_string()))
+}
+
+// 时间窗口内的提交，返回提交数和给模型的描述
+fn git_repo_activity(
+    repo: &str,
+    since: &str,
+    until: Option<&str>,
+    authors: &[String],
+) -> Result<(usize, String), Box<dyn Error>> {
+    let mut args: Vec<String> = vec![
+        String::from("-C"),
+        repo.to_string(),
+        String::from("log"),
+        // 所有本地和远程分支，不包含 stash
+        String::from("--branches"),
+        String::from("--remotes"),
+        String::from("--no-merges"),
+        String::from("--no-color"),
+        format!("--since={}", since),
+        String::from("--date=format:%Y-%m-%d %H:%M"),
+        format!("--stat=100,80,{}", STAT_FILES),
+        String::from("--format=%x1e%h %ad %an%n%B"),
+    ];
+    if let Some(until) = until {
+        args.push(format!("--until={}", until));
+    }
+    for author in resolve_authors(repo, authors)?.iter() {
+        args.push(format!("--author={}", author));
+    }
+
+    let output = git_output(&args)?;
+    let commits: Vec<&str> = output.split('\x1e').map(|c| c.trim()).filter(|c| !c.is_empty()).collect();
+    Ok((commits.len(), commits.join("\n\n")))
+}
+
+pub fn handle_summary(
+    since: String,
+    until: Option<String>,
+    authors: Vec<String>,
+    repos: Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    let repos = if repos.is_empty() { vec![String::from(".")] } else { repos };
+
+    let mut total = 0;
+    let mut content = String::new();
+    for repo in repos.iter() {
+        let name = repo_name(repo.as_str())?;
+        let (count, commits) = git_repo_activity(repo.as_str(), since.as_str(), until.as_deref(), &authors)?;
+        eprintln!("{}: {} commit(s)", name, count);
+        if count == 0 {
+            continue;
+        }
+        total += count;
+        content.push_str(format!("\n## Repository {}\n\n{}\n", name, commits).as_str());
+    }
+
+    let mut window = format!("since {}", since);
+    if let Some(until) = until.as_deref() {
+        window.push_str(format!(" until {}", until).as_str());
+    }
+    if !authors.is_empty() {
+        window.push_str(format!(" by {}", authors.join(", ")).as_str());
+    }
+    if total == 0 {
+        println!("No commits {}.", window);
+        return Ok(());
+    }
+
+    eprintln!("Summarizing {} commit(s)...", total);
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let summary = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_summary(window, prepare_for_ai(content)?),
+    )?;
+    println!("{}", summary.trim());
+    Ok(())
+}
@@ -0,0 +1,58 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use regex::Regex;
+use std::error::Error;
+
+use crate::command::git_output;
+use crate::config::{G_TICKET_FOOTER, G_TICKET_PATTERN, G_TICKET_PLACEMENT};
+
+// 获取当前分支名，处于 detached HEAD 时返回空字符串
+pub fn git_current_branch() -> Result<String, Box<dyn Error>> {
+    match git_output(&["symbolic-ref", "--quiet", "--short", "HEAD"]) {
+        Ok(branch) => Ok(branch.trim().to_string()),
+        Err(_) => Ok(String::new()),
+    }
+}
+
+// 从分支名中提取 ticket，例如 feature/PROJ-1234-add-login -> PROJ-1234
+pub fn extract_tickets(branch: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let re = Regex::new(G_TICKET_PATTERN.as_str())?;
+
+    let mut tickets: Vec<String> = vec![];
+    for caps in re.captures_iter(branch) {
+        // 有捕获组时取第一个捕获组，否则取整个匹配
+        let ticket = caps.get(1).or(caps.get(0)).map(|m| m.as_str().to_string());
+        if let Some(ticket) = ticket
+            && !tickets.contains(&ticket)
+        {
+            tickets.push(ticket);
+        }
+    }
+    Ok(tickets)
+}
+
+// 当前分支关联的 ticket
+pub fn branch_tickets() -> Result<Vec<String>, Box<dyn Error>> {
+    let branch = git_current_branch()?;
+    let tickets = extract_tickets(branch.as_str())?;
+    debug!("branch: {:?}, tickets: {:?}", branch, tickets);
+    Ok(tickets)
+}
+
+// 按配置把 ticket 写入 commit message：footer 追加 `Refs: PROJ-1234`，prefix 写在 subject 之前
+pub fn apply_tickets(message: String, tickets: &[String]) -> String {
+    let missing: Vec<&String> = tickets.iter().filter(|t| !message.contains(t.as_str())).collect();
+    if missing.is_empty() {
+        return message;
+    }
+    let refs = missing.iter().map(|t| t.as_str()).collect::<Vec<&str>>().join(", ");
+
+    match G_TICKET_PLACEMENT.as_str() {
+        "footer" => format!("{}\n\n{}: {}", message.trim_end(), G_TICKET_FOOTER.as_str(), refs),
+        "prefix" => {
+            let prefix = missing.iter().map(|t| t.as_str()).collect::<Vec<&str>>().join(" ");
+            format!("{} {}", prefix, message.trim_start())
+        }
+        _ => message,
+    }
+}
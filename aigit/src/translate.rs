@@ -0,0 +1,411 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::error::Error;
+use std::io::IsTerminal;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_foreground, git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM};
+use crate::split::read_line;
+
+// 只读的 git 子命令
+const READ_ONLY_COMMANDS: [&str; 16] = [
+    "status", "log", "show", "diff", "blame", "describe", "rev-parse", "rev-list", "ls-files", "ls-tree",
+    "shortlog", "grep", "cat-file", "for-each-ref", "show-ref", "merge-base",
+];
+
+// 改写历史、丢弃修改或影响远程仓库的 git 子命令，update-ref 移动分支指针可能让提交不可达
+const DESTRUCTIVE_COMMANDS: [&str; 7] = ["push", "rebase", "filter-branch", "filter-repo", "gc", "prune", "update-ref"];
+
+// 命令的风险等级，顺序即严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Risk {
+    ReadOnly,
+    LocalMutating,
+    Destructive,
+}
+
+impl Risk {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Risk::ReadOnly => "read-only",
+            Risk::LocalMutating => "local-mutating",
+            Risk::Destructive => "history/remote-destructive",
+        }
+    }
+
+    fn parse(s: &str) -> Risk {
+        match s.trim().to_lowercase().as_str() {
+            // 模型没有给出时以本地规则为准
+            "" | "read-only" => Risk::ReadOnly,
+            "local-mutating" => Risk::LocalMutating,
+            _ => Risk::Destructive,
+        }
+    }
+}
+
+// 模型给出的一条 git 命令，args 不含开头的 `git`
+#[derive(Debug, Deserialize)]
+struct PlannedCommand {
+    args: Vec<String>,
+    #[serde(default)]
+    risk: String,
+    #[serde(default)]
+    explanation: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandPlan {
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    commands: Vec<PlannedCommand>,
+}
+
+fn prompt_do(request: String, state: String) -> Vec<ChatMessage> {
+    let mut do_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a Git expert translating a request in natural language into git commands. The user will provide the request and the current state of the repository.
+
+For every command:
+    - `args`: the arguments after `git`, one array item per argument, e.g. [\"reset\", \"--soft\", \"HEAD~1\"]; only git commands, no shell syntax, pipes or other programs
+    - `risk`: one of `read-only` (only reads), `local-mutating` (changes the working tree, index or refs but keeps all work recoverable), `history/remote-destructive` (rewrites or discards commits, discards uncommitted changes, deletes branches or tags, or changes a remote)
+    - `explanation`: one sentence describing what the command does in this repository
+
+Use the fewest and safest commands that fulfil the request. If the request cannot be done with git, return no commands and explain why in the summary.
+Output ONLY a JSON object with no additional text and no markdown, for example:
+{\"summary\": \"Undo the last commit and keep its changes staged\", \"commands\": [{\"args\": [\"reset\", \"--soft\", \"HEAD~1\"], \"risk\": \"history/remote-destructive\", \"explanation\": \"Moves the branch back one commit, the changes stay staged\"}]}"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    do_msgs[1].content.push_str(format!("Request: {}\n\n### Repository state\n{}", request, state).as_str());
+    do_msgs
+}
+
+// 分支、工作区状态和最近的提交，帮助模型给出准确的命令
+fn git_repo_state() -> String {
+    let status = git_output(&["status", "--short", "--branch"]).unwrap_or_default();
+    let log = git_output(&["log", "--oneline", "-n5"]).unwrap_or_default();
+    format!("git status --short --branch:\n{}\ngit log --oneline -n5:\n{}", status, log)
+}
+
+fn has_arg(args: &[String], options: &[&str]) -> bool {
+    args.iter().any(|a| options.contains(&a.as_str()))
+}
+
+// 已有的提交或分支，包括 checkout 时会自动创建的同名远程分支
+fn git_is_rev(name: &str) -> bool {
+    if git_output(&["rev-parse", "-q", "--verify", format!("{}^{{commit}}", name).as_str()]).is_ok() {
+        return true;
+    }
+    git_output(&["for-each-ref", "--count=1", "--format=%(refname)", format!("refs/remotes/*/{}", name).as_str()])
+        .is_ok_and(|r| !r.trim().is_empty())
+}
+
+// 本地规则分类，不依赖模型的判断；is_rev 判断 checkout 的参数是分支还是路径
+fn classify(args: &[String], is_rev: &dyn Fn(&str) -> bool) -> Risk {
+    let Some(subcommand) = args.first() else {
+        return Risk::ReadOnly;
+    };
+    let rest = &args[1..];
+    // `git -c ...` 等全局选项可以执行任意程序，一律当作高风险
+    if subcommand.starts_with('-') {
+        return Risk::Destructive;
+    }
+    // log、diff 等的 --output 会覆盖任意文件
+    if rest.iter().any(|a| a == "--output" || a.starts_with("--output=")) {
+        return Risk::Destructive;
+    }
+    if READ_ONLY_COMMANDS.contains(&subcommand.as_str()) {
+        return Risk::ReadOnly;
+    }
+    if DESTRUCTIVE_COMMANDS.contains(&subcommand.as_str()) {
+        return Risk::Destructive;
+    }
+
+    let positional: Vec<&String> = rest.iter().filter(|a| !a.starts_with('-')).collect();
+    match subcommand.as_str() {
+        // 移动分支指针或丢弃修改
+        "reset" => {
+            if has_arg(rest, &["--hard", "--merge", "--keep"]) || !positional.is_empty() {
+                Risk::Destructive
+            } else {
+                Risk::LocalMutating
+            }
+        }
+        "commit" if has_arg(rest, &["--amend"]) => Risk::Destructive,
+        "clean" => {
+            if has_arg(rest, &["-n", "--dry-run"]) {
+                Risk::ReadOnly
+            } else {
+                Risk::Destructive
+            }
+        }
+        // -B、-C 会重置已有的分支
+        "checkout" | "switch" if has_arg(rest, &["-f", "--force", "--discard-changes", "-B", "-C", "--force-create", "--"]) => {
+            Risk::Destructive
+        }
+        "checkout" => {
+            if has_arg(rest, &["-b", "--orphan"]) {
+                // 后面是新分支名和起点
+                Risk::LocalMutating
+            } else {
+                match positional.as_slice() {
+                    [] => Risk::LocalMutating,
+                    [rev] if is_rev(rev.as_str()) => Risk::LocalMutating,
+                    // 其余都是路径，会丢弃工作区的修改
+                    _ => Risk::Destructive,
+                }
+            }
+        }
+        "restore" if !has_arg(rest, &["--staged", "-S"]) || has_arg(rest, &["--worktree", "-W"]) => Risk::Destructive,
+        "rm" if !has_arg(rest, &["--cached"]) => Risk::Destructive,
+        // 覆盖已有的文件
+        "mv" if has_arg(rest, &["-f", "--force"]) => Risk::Destructive,
+        // 丢弃进行中的 merge 等操作和其中已解决的冲突
+        "merge" | "cherry-pick" | "revert" | "am" if has_arg(rest, &["--abort"]) => Risk::Destructive,
+        "branch" => {
+            if has_arg(rest, &["-d", "-D", "--delete", "-f", "--force", "-m", "-M", "--move", "-C"]) {
+                Risk::Destructive
+            } else if positional.is_empty() {
+                Risk::ReadOnly
+            } else {
+                Risk::LocalMutating
+            }
+        }
+        "tag" => {
+            if has_arg(rest, &["-d", "--delete", "-f", "--force"]) {
+                Risk::Destructive
+            } else if positional.is_empty() || has_arg(rest, &["-l", "--list"]) {
+                Risk::ReadOnly
+            } else {
+                Risk::LocalMutating
+            }
+        }
+        "stash" => match positional.first().map(|s| s.as_str()) {
+            Some("drop") | Some("clear") => Risk::Destructive,
+            Some("list") | Some("show") => Risk::ReadOnly,
+            _ => Risk::LocalMutating,
+        },
+        "remote" => match positional.first().map(|s| s.as_str()) {
+            None | Some("show") | Some("get-url") => Risk::ReadOnly,
+            Some("remove") | Some("rm") | Some("prune") | Some("set-url") => Risk::Destructive,
+            _ => Risk::LocalMutating,
+        },
+        "reflog" => match positional.first().map(|s| s.as_str()) {
+            None | Some("show") => Risk::ReadOnly,
+            _ => Risk::Destructive,
+        },
+        // alias.*、core.pager、core.hooksPath 等配置可以执行任意程序，任何写入都当作高风险
+        "config" => {
+            let writes = has_arg(
+                rest,
+                &["--unset", "--unset-all", "--add", "--replace-all", "--rename-section", "--remove-section", "-e", "--edit"],
+            ) || matches!(
+                positional.first().map(|s| s.as_str()),
+                Some("set") | Some("unset") | Some("rename-section") | Some("remove-section") | Some("edit")
+            );
+            let reads = has_arg(rest, &["--get", "--get-all", "--get-regexp", "--get-urlmatch", "--list", "-l"])
+                || matches!(positional.first().map(|s| s.as_str()), Some("get") | Some("list"))
+                || positional.len() <= 1;
+            if reads && !writes {
+                Risk::ReadOnly
+            } else {
+                Risk::Destructive
+            }
+        }
+        "worktree" if has_arg(rest, &["--force", "-f", "remove"]) => Risk::Destructive,
+        _ => Risk::LocalMutating,
+    }
+}
+
+fn ask_plan(request: &str) -> Result<CommandPlan, Box<dyn Error>> {
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let answer = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_do(request.to_string(), prepare_for_ai(git_repo_state())?),
+    )?;
+
+    let (Some(start), Some(end)) = (answer.find('{'), answer.rfind('}')) else {
+        return Err(format!("Fail to find the commands in the response:\n{}", answer).into());
+    };
+    if start > end {
+        return Err(format!("Fail to find the commands in the response:\n{}", answer).into());
+    }
+    Ok(serde_json::from_str(&answer[start..=end])?)
+}
+
+// 展示用，包含空格等特殊字符的参数加引号
+fn display_command(args: &[String]) -> String {
+    let mut line = String::from("git");
+    for arg in args.iter() {
+        if arg.is_empty() || arg.chars().any(|c| c.is_whitespace() || "'\"$`\\*?;&|<>()".contains(c)) {
+            line.push_str(format!(" '{}'", arg.replace('\'', "'\\''")).as_str());
+        } else {
+            line.push_str(format!(" {}", arg).as_str());
+        }
+    }
+    line
+}
+
+// 风险越高，需要的确认越明确
+fn confirm(risk: Risk, yes: bool) -> Result<bool, Box<dyn Error>> {
+    match risk {
+        Risk::ReadOnly => Ok(true),
+        Risk::LocalMutating if yes => Ok(true),
+        Risk::LocalMutating => {
+            if !std::io::stdin().is_terminal() {
+                return Err("Not running in a terminal, use --yes to run commands that change the repository".into());
+            }
+            Ok(read_line("Run these commands? [y/N] ")?.eq_ignore_ascii_case("y"))
+        }
+        Risk::Destructive => {
+            if !std::io::stdin().is_terminal() {
+                return Err("Not running in a terminal, destructive commands must be confirmed interactively".into());
+            }
+            println!("\nWarning: the plan rewrites history, discards changes or changes a remote.");
+            Ok(read_line("Type `yes` to run these commands: ")? == "yes")
+        }
+    }
+}
+
+pub fn handle_do(request: String, dry_run: bool, yes: bool) -> Result<(), Box<dyn Error>> {
+    eprintln!("Translating...");
+    let plan = ask_plan(request.as_str())?;
+    let commands: Vec<(Vec<String>, Risk, &str)> = plan
+        .commands
+        .iter()
+        .filter(|c| !c.args.is_empty())
+        .map(|c| {
+            // 去掉模型可能加上的 git 前缀，风险取本地规则和模型判断中较高的一个
+            let args = if c.args[0] == "git" { c.args[1..].to_vec() } else { c.args.clone() };
+            let risk = classify(&args, &git_is_rev).max(Risk::parse(c.risk.as_str()));
+            (args, risk, c.explanation.as_str())
+        })
+        .collect();
+
+    println!("============================================================================");
+    println!("{}", if plan.summary.is_empty() { "Plan" } else { plan.summary.as_str() });
+    println!("============================================================================");
+    if commands.is_empty() {
+        println!("No git commands for this request.");
+        return Ok(());
+    }
+    for (idx, (args, risk, explanation)) in commands.iter().enumerate() {
+        println!("{}. {}", idx + 1, display_command(args));
+        println!("   [{}] {}", risk.as_str(), explanation);
+    }
+
+    if dry_run {
+        println!("\nDry run, nothing was executed.");
+        return Ok(());
+    }
+    let risk = commands.iter().map(|(_, risk, _)| *risk).max().unwrap_or(Risk::ReadOnly);
+    if !confirm(risk, yes)? {
+        println!("Cancelled, nothing was executed.");
+        return Ok(());
+    }
+
+    for (args, _, _) in commands.iter() {
+        println!("\n$ {}", display_command(args));
+        git_foreground(args, format!("`{}` failed", display_command(args)).as_str()).inspect_err(|_| {
+            eprintln!("The remaining commands were not run.");
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn risk(command: &str) -> Risk {
+        let args: Vec<String> = command.split_whitespace().map(|a| a.to_string()).collect();
+        classify(&args, &|name| ["main", "HEAD~1", "v1.0"].contains(&name))
+    }
+
+    #[test]
+    fn read_only_commands() {
+        for command in [
+            "status",
+            "log --oneline -n5",
+            "diff --cached",
+            "branch",
+            "tag -l",
+            "stash list",
+            "clean -n",
+            "config user.email",
+            "config --get core.pager",
+            "config --global --list",
+            "config get user.name",
+        ] {
+            assert_eq!(risk(command), Risk::ReadOnly, "{}", command);
+        }
+    }
+
+    #[test]
+    fn local_mutating_commands() {
+        for command in [
+            "add src/main.rs",
+            "checkout main",
+            "checkout -",
+            "checkout -b feature main",
+            "switch -c feature",
+            "switch main",
+            "branch feature",
+            "restore --staged src/main.rs",
+            "reset",
+            "stash",
+            "mv a.rs b.rs",
+            "merge feature",
+        ] {
+            assert_eq!(risk(command), Risk::LocalMutating, "{}", command);
+        }
+    }
+
+    #[test]
+    fn destructive_commands() {
+        for command in [
+            "push origin main",
+            "reset --hard HEAD~1",
+            "reset HEAD~1",
+            "checkout src/main.rs",
+            "checkout .",
+            "checkout HEAD~1 src/main.rs",
+            "checkout -- src/main.rs",
+            "checkout -B main",
+            "switch -C main",
+            "branch -C main backup",
+            "branch -D feature",
+            "restore src/main.rs",
+            "update-ref refs/heads/main 1a2b3c",
+            "update-ref -d refs/heads/main",
+            "log --output=Cargo.toml",
+            "diff --output Cargo.toml",
+            "commit --amend",
+            "clean -fd",
+            "-c core.pager=sh status",
+            "config alias.st !rm -rf .",
+            "config core.pager less",
+            "config --global core.hooksPath /tmp/hooks",
+            "config --system core.sshCommand ssh",
+            "config --unset user.email",
+            "config set core.editor vim",
+            "mv -f a.rs b.rs",
+            "merge --abort",
+            "rebase --abort",
+            "cherry-pick --abort",
+        ] {
+            assert_eq!(risk(command), Risk::Destructive, "{}", command);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::error::Error;
+
+use crate::api::common::{ChatFn, ChatMessage, get_chat};
+use crate::command::{git_output, prepare_for_ai};
+use crate::config::{G_AI_API_KEY, G_AI_MODEL, G_AI_PLATFORM, repo_root};
+
+// 展示给模型的当前代码上下文行数
+const CODE_CONTEXT_LINES: usize = 5;
+
+fn prompt_why(location: String, code: String, blame: String, history: String) -> Vec<ChatMessage> {
+    let mut why_msgs: Vec<ChatMessage> = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content:
+"You are a senior software engineer explaining the history of a line of code to a new team member. The user will provide the current code around the line, the `git blame` result and the commits that introduced and modified the line (from `git log -L`), newest first.
+
+### Output Specification:
+    - Start with a one-sentence answer to \"why does this line look like this?\"
+    - Then tell the story chronologically, from the commit that introduced the line to the latest change
+    - Cite the short commit hash in backticks (e.g. `1a2b3c4`) for every claim
+    - Quote the commit messages when they explain the intent
+    - Say explicitly when the history does not explain the reason, do not speculate about context not present in the history"
+            .to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        },
+    ];
+
+    why_msgs[1].content.push_str(format!("Please explain why {} exists.\n", location).as_str());
+    why_msgs[1].content.push_str("\n### Current code\n");
+    why_msgs[1].content.push_str(code.as_str());
+    why_msgs[1].content.push_str("\n### Blame\n");
+    why_msgs[1].content.push_str(blame.as_str());
+    why_msgs[1].content.push_str("\n### History\n");
+    why_msgs[1].content.push_str(history.as_str());
+    why_msgs
+}
+
+fn parse_location(location: &str) -> Result<(String, usize), Box<dyn Error>> {
+    let Some((file, line)) = location.rsplit_once(':') else {
+        return Err(format!("Invalid location '{}', expected <file>:<line>", location).into());
+    };
+    let line = line
+        .parse::<usize>()
+        .ok()
+        .filter(|l| *l > 0)
+        .ok_or(format!("Invalid line number in '{}'", location))?;
+    Ok((file.to_string(), line))
+}
+
+// 行号附近的当前代码，目标行用 `>` 标记
+// 读取 HEAD 中的文件，和 `git log -L`、`git blame HEAD` 使用同一个版本
+fn code_context(file: &str, line: usize) -> Result<String, Box<dyn Error>> {
+    // 相对路径以当前目录为准，绝对路径转换成相对于仓库根目录的路径
+    let spec = match repo_root().and_then(|root| file.strip_prefix(format!("{}/", root).as_str()).map(|p| p.to_string())) {
+        Some(path) => format!("HEAD:{}", path),
+        None => format!("HEAD:./{}", file),
+    };
+    let content = git_output(&["show", spec.as_str()])?;
+    let lines: Vec<&str> = content.lines().collect();
+    if line > lines.len() {
+        return Err(format!("{} has only {} lines", file, lines.len()).into());
+    }
+
+    let start = line.saturating_sub(CODE_CONTEXT_LINES + 1);
+    let end = (line + CODE_CONTEXT_LINES).min(lines.len());
+    let mut code = String::new();
+    for (idx, text) in lines[start..end].iter().enumerate() {
+        let number = start + idx + 1;
+        let marker = if number == line { ">" } else { " " };
+        code.push_str(format!("{}{:>5} | {}\n", marker, number, text).as_str());
+    }
+    Ok(code)
+}
+
+fn git_blame_line(file: &str, line: usize) -> Result<String, Box<dyn Error>> {
+    git_output(&[
+        "blame".to_string(),
+        "--date=short".to_string(),
+        format!("-L{},{}", line, line),
+        "HEAD".to_string(),
+        "--".to_string(),
+        file.to_string(),
+    ])
+}
+
+// `git log -L` 跟踪该行的所有修改，包含提交信息和 diff
+fn git_line_history(file: &str, line: usize, number: u32) -> Result<String, Box<dyn Error>> {
+    git_output(&[
+        "log".to_string(),
+        "--no-color".to_string(),
+        format!("-n{}", number),
+        "--date=short".to_string(),
+        format!("-L{},{}:{}", line, line, file),
+    ])
+}
+
+pub fn handle_why(location: String, number: u32) -> Result<(), Box<dyn Error>> {
+    let (file, line) = parse_location(location.as_str())?;
+    // 行号按 HEAD 中的版本解释，本地修改可能让它指向另一行
+    if git_output(&["diff", "HEAD", "--quiet", "--", file.as_str()]).is_err() {
+        eprintln!("Warning: {} has uncommitted changes, line {} refers to the version at HEAD", file, line);
+    }
+    let code = code_context(file.as_str(), line)?;
+    let blame = git_blame_line(file.as_str(), line)?;
+    let history = git_line_history(file.as_str(), line, number)?;
+
+    println!("============================================================================");
+    println!("Git Blame");
+    println!("============================================================================");
+    println!("{}", blame.trim_end());
+
+    println!("============================================================================");
+    println!("Explaining...\n");
+    let chat: ChatFn = get_chat(G_AI_PLATFORM.clone());
+    let why = chat(
+        G_AI_MODEL.clone(),
+        G_AI_API_KEY.clone(),
+        prompt_why(
+            location,
+            prepare_for_ai(code)?,
+            prepare_for_ai(blame)?,
+            prepare_for_ai(history)?,
+        ),
+    )?;
+    println!("{}", why);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// 不调用模型的命令在没有 ~/.config/aigit/config.toml 时也要能运行
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// 临时的 HOME 和 git 仓库，HOME 下没有 aigit 配置
+fn scratch_repo(name: &str) -> (PathBuf, PathBuf) {
+    let base = std::env::temp_dir().join(format!("aigit-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&base);
+    let home = base.join("home");
+    let repo = base.join("repo");
+    std::fs::create_dir_all(&home).unwrap();
+    std::fs::create_dir_all(&repo).unwrap();
+
+    git(&home, &repo, &["init", "-q"]);
+    std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+    git(&home, &repo, &["add", "a.txt"]);
+    git(&home, &repo, &["commit", "-q", "-m", "init"]);
+    std::fs::write(repo.join("a.txt"), "a\nb\n").unwrap();
+    (home, repo)
+}
+
+fn git(home: &Path, repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn aigit(home: &Path, repo: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aigit"))
+        .args(args)
+        .current_dir(repo)
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn diff_without_config() {
+    let (home, repo) = scratch_repo("diff");
+    let output = aigit(&home, &repo, &["diff"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("+b"), "{}", stdout);
+}
+
+#[test]
+fn commit_without_config() {
+    let (home, repo) = scratch_repo("commit");
+    git(&home, &repo, &["add", "a.txt"]);
+    let output = aigit(&home, &repo, &["commit", "-d"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}